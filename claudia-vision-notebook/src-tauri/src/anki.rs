@@ -0,0 +1,380 @@
+use rusqlite::{params, Connection};
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Fixed note type id so repeated imports reuse the same Anki note type
+const MODEL_ID: i64 = 1_718_064_000_000;
+
+const MODEL_NAME: &str = "Claudia Video Card";
+
+const FIELDS: [&str; 6] = ["Front", "Back", "Image", "Title", "Channel", "Link"];
+
+const QUESTION_TEMPLATE: &str =
+    "{{Front}}{{#Image}}<div class=\"frame\">{{Image}}</div>{{/Image}}";
+
+const ANSWER_TEMPLATE: &str =
+    "{{FrontSide}}<hr id=answer>{{Back}}\
+     <div class=\"source\"><a href=\"{{Link}}\">{{Title}}</a> &middot; {{Channel}}</div>";
+
+const CARD_CSS: &str = ".card { font-family: arial; font-size: 20px; text-align: left; }\n\
+.frame img { max-width: 100%; margin-top: 12px; }\n\
+.source { margin-top: 16px; font-size: 14px; color: #666; }";
+
+/// Legacy (schema 11) collection layout, which every Anki version imports
+const COLLECTION_SCHEMA: &str = "
+    CREATE TABLE col (
+        id integer primary key, crt integer not null, mod integer not null,
+        scm integer not null, ver integer not null, dty integer not null,
+        usn integer not null, ls integer not null, conf text not null,
+        models text not null, decks text not null, dconf text not null, tags text not null
+    );
+    CREATE TABLE notes (
+        id integer primary key, guid text not null, mid integer not null,
+        mod integer not null, usn integer not null, tags text not null,
+        flds text not null, sfld integer not null, csum integer not null,
+        flags integer not null, data text not null
+    );
+    CREATE TABLE cards (
+        id integer primary key, nid integer not null, did integer not null,
+        ord integer not null, mod integer not null, usn integer not null,
+        type integer not null, queue integer not null, due integer not null,
+        ivl integer not null, factor integer not null, reps integer not null,
+        lapses integer not null, left integer not null, odue integer not null,
+        odid integer not null, flags integer not null, data text not null
+    );
+    CREATE TABLE revlog (
+        id integer primary key, cid integer not null, usn integer not null,
+        ease integer not null, ivl integer not null, lastIvl integer not null,
+        factor integer not null, time integer not null, type integer not null
+    );
+    CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+    CREATE INDEX ix_notes_usn ON notes (usn);
+    CREATE INDEX ix_cards_usn ON cards (usn);
+    CREATE INDEX ix_revlog_usn ON revlog (usn);
+    CREATE INDEX ix_cards_nid ON cards (nid);
+    CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid ON revlog (cid);
+    CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+/// One card in an exported deck
+///
+/// `front` and `back` are HTML; the other fields are plain text.
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    /// Stable id so re-importing an export updates notes instead of duplicating them
+    pub guid: String,
+    pub front: String,
+    pub back: String,
+    /// Frame shown under the question
+    pub image: Option<PathBuf>,
+    pub title: String,
+    pub channel: String,
+    /// Link that opens the video where the answer is discussed
+    pub link: String,
+}
+
+/// Write `notes` as a single-deck Anki package (`.apkg`)
+///
+/// The package is a zip holding a SQLite collection, the referenced images
+/// stored under numeric names, and a `media` JSON map from those names to
+/// the file names the cards use.
+pub fn write_package(path: &Path, deck_name: &str, notes: &[AnkiNote]) -> Result<(), String> {
+    let workdir = tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let collection_path = workdir.path().join("collection.anki2");
+
+    let media = media_names(notes);
+    write_collection(&collection_path, deck_name, notes, &media)?;
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Frames are already compressed
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let collection = std::fs::read(&collection_path)
+        .map_err(|e| format!("Failed to read collection: {}", e))?;
+    add_zip_entry(&mut zip, "collection.anki2", &collection, deflated)?;
+
+    let mut media_map = BTreeMap::new();
+    for (index, (source, name)) in media.iter().enumerate() {
+        let bytes = std::fs::read(source)
+            .map_err(|e| format!("Failed to read frame {}: {}", source.display(), e))?;
+        add_zip_entry(&mut zip, &index.to_string(), &bytes, stored)?;
+        media_map.insert(index.to_string(), name.clone());
+    }
+    let media_json = serde_json::to_vec(&media_map).map_err(|e| e.to_string())?;
+    add_zip_entry(&mut zip, "media", &media_json, deflated)?;
+
+    zip.finish().map_err(|e| format!("Failed to write package: {}", e))?;
+    Ok(())
+}
+
+/// Escape text for use inside an HTML field
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Helper functions
+
+/// File name inside the package for each distinct image, in first-use order
+fn media_names(notes: &[AnkiNote]) -> Vec<(PathBuf, String)> {
+    let mut media: Vec<(PathBuf, String)> = Vec::new();
+    for image in notes.iter().filter_map(|note| note.image.as_ref()) {
+        if media.iter().any(|(source, _)| source == image) {
+            continue;
+        }
+        // Frame names repeat across videos, so prefix a hash of the full path
+        let digest = Sha1::digest(image.to_string_lossy().as_bytes());
+        let file_name = image.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        media.push((image.clone(), format!("{}_{}", hex_prefix(&digest, 8), file_name)));
+    }
+    media
+}
+
+fn write_collection(
+    path: &Path,
+    deck_name: &str,
+    notes: &[AnkiNote],
+    media: &[(PathBuf, String)],
+) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to create collection: {}", e))?;
+    conn.execute_batch(COLLECTION_SCHEMA)
+        .map_err(|e| format!("Failed to create collection: {}", e))?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let now = now_ms / 1000;
+    let deck_id = deck_id(deck_name);
+
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            now - now % 86_400,
+            now_ms,
+            collection_conf(deck_id).to_string(),
+            json!({ MODEL_ID.to_string(): note_type(deck_id, now) }).to_string(),
+            json!({ "1": deck(1, "Default", now), deck_id.to_string(): deck(deck_id, deck_name, now) }).to_string(),
+            json!({ "1": deck_options() }).to_string(),
+        ],
+    ).map_err(|e| format!("Failed to write collection: {}", e))?;
+
+    for (i, note) in notes.iter().enumerate() {
+        // Anki ids are millisecond timestamps; offsets keep them unique
+        let note_id = now_ms + i as i64;
+        let image = note.image.as_ref()
+            .and_then(|image| media.iter().find(|(source, _)| source == image))
+            .map(|(_, name)| format!("<img src=\"{}\">", escape_html(name)))
+            .unwrap_or_default();
+        let fields = [
+            note.front.clone(),
+            note.back.clone(),
+            image,
+            escape_html(&note.title),
+            escape_html(&note.channel),
+            escape_html(&note.link),
+        ];
+        let sort_field = strip_html(&note.front);
+
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, '', ?5, ?6, ?7, 0, '')",
+            params![note_id, &note.guid, MODEL_ID, now, fields.join("\x1f"), &sort_field, checksum(&sort_field)],
+        ).map_err(|e| format!("Failed to write note: {}", e))?;
+
+        conn.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![note_id, note_id, deck_id, now, i as i64 + 1],
+        ).map_err(|e| format!("Failed to write card: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn add_zip_entry(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    bytes: &[u8],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    zip.start_file(name, options)
+        .and_then(|_| zip.write_all(bytes).map_err(Into::into))
+        .map_err(|e| format!("Failed to write {} to package: {}", name, e))
+}
+
+/// Deck id derived from the name, so exports with the same name land in one deck
+fn deck_id(deck_name: &str) -> i64 {
+    let digest = Sha1::digest(deck_name.as_bytes());
+    let id = i64::from_str_radix(&hex_prefix(&digest, 12), 16).unwrap_or(0);
+    id.max(2)
+}
+
+/// Duplicate-detection checksum Anki stores for the sort field
+fn checksum(sort_field: &str) -> i64 {
+    let digest = Sha1::digest(sort_field.as_bytes());
+    i64::from_str_radix(&hex_prefix(&digest, 8), 16).unwrap_or(0)
+}
+
+fn hex_prefix(bytes: &[u8], len: usize) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()[..len].to_string()
+}
+
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&amp;", "&")
+}
+
+fn collection_conf(deck_id: i64) -> serde_json::Value {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": MODEL_ID.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true
+    })
+}
+
+fn note_type(deck_id: i64, now: i64) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| json!({
+            "name": name, "ord": ord, "sticky": false, "rtl": false,
+            "font": "Arial", "size": 20, "media": []
+        }))
+        .collect();
+
+    json!({
+        "id": MODEL_ID,
+        "name": MODEL_NAME,
+        "type": 0,
+        "mod": now,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "tmpls": [{
+            "name": "Card 1", "ord": 0, "qfmt": QUESTION_TEMPLATE, "afmt": ANSWER_TEMPLATE,
+            "did": null, "bqfmt": "", "bafmt": ""
+        }],
+        "flds": fields,
+        "css": CARD_CSS,
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "tags": [],
+        "vers": [],
+        "req": [[0, "any", [0]]]
+    })
+}
+
+fn deck(id: i64, name: &str, now: i64) -> serde_json::Value {
+    json!({
+        "id": id, "name": name, "desc": "", "mod": now, "usn": -1,
+        "dyn": 0, "conf": 1, "collapsed": false, "browserCollapsed": false,
+        "extendNew": 10, "extendRev": 50,
+        "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0]
+    })
+}
+
+fn deck_options() -> serde_json::Value {
+    json!({
+        "id": 1, "name": "Default", "mod": 0, "usn": 0,
+        "maxTaken": 60, "autoplay": true, "timer": 0, "replayq": true, "dyn": false,
+        "new": {
+            "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+            "order": 1, "perDay": 20, "bury": true, "separate": true
+        },
+        "rev": { "perDay": 100, "ease4": 1.3, "fuzz": 0.05, "maxIvl": 36500, "ivlFct": 1, "bury": true, "minSpace": 1 },
+        "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn note(guid: &str, front: &str, image: Option<PathBuf>) -> AnkiNote {
+        AnkiNote {
+            guid: guid.to_string(),
+            front: front.to_string(),
+            back: "<b>B. It moves</b>".to_string(),
+            image,
+            title: "Ownership & Borrowing".to_string(),
+            channel: "Crab Academy".to_string(),
+            link: "https://youtu.be/dQw4w9WgXcQ?t=95".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_package_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let frame = dir.path().join("frame_000047.jpg");
+        std::fs::write(&frame, b"jpeg bytes").unwrap();
+        let package = dir.path().join("deck.apkg");
+
+        let notes = vec![
+            note("a1", "What happens to <code>s</code>?", Some(frame.clone())),
+            note("b2", "Who owns the string?", Some(frame)),
+            note("c3", "No frame here", None),
+        ];
+        write_package(&package, "Rust::Ownership", &notes).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&package).unwrap()).unwrap();
+
+        // One media file even though two cards show it
+        let mut media = String::new();
+        archive.by_name("media").unwrap().read_to_string(&mut media).unwrap();
+        let media: BTreeMap<String, String> = serde_json::from_str(&media).unwrap();
+        assert_eq!(media.len(), 1);
+        assert!(media["0"].ends_with("_frame_000047.jpg"));
+        let mut bytes = Vec::new();
+        archive.by_name("0").unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, b"jpeg bytes");
+
+        let collection_path = dir.path().join("collection.anki2");
+        let mut collection = Vec::new();
+        archive.by_name("collection.anki2").unwrap().read_to_end(&mut collection).unwrap();
+        std::fs::write(&collection_path, collection).unwrap();
+
+        let conn = Connection::open(&collection_path).unwrap();
+        let cards: i64 = conn.query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0)).unwrap();
+        assert_eq!(cards, 3);
+
+        let (flds, sfld): (String, String) = conn.query_row(
+            "SELECT flds, sfld FROM notes WHERE guid = 'a1'", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        let fields: Vec<&str> = flds.split('\x1f').collect();
+        assert_eq!(fields.len(), FIELDS.len());
+        assert_eq!(fields[2], format!("<img src=\"{}\">", media["0"]));
+        assert_eq!(fields[3], "Ownership &amp; Borrowing");
+        assert_eq!(fields[5], "https://youtu.be/dQw4w9WgXcQ?t=95");
+        assert_eq!(sfld, "What happens to s?");
+
+        let decks: String = conn.query_row("SELECT decks FROM col", [], |row| row.get(0)).unwrap();
+        let decks: serde_json::Value = serde_json::from_str(&decks).unwrap();
+        assert_eq!(decks[deck_id("Rust::Ownership").to_string()]["name"], "Rust::Ownership");
+    }
+}
@@ -0,0 +1,302 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::commands::vision_notebook::TranscriptSegment;
+use crate::manifest::Chapter;
+
+/// YouTube only shows description chapters when there are at least this many
+const MIN_DESCRIPTION_CHAPTERS: usize = 3;
+
+/// Words too common to say anything about a topic
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "before",
+    "being", "but", "can", "could", "did", "does", "doing", "don", "down", "each", "even", "for",
+    "from", "get", "going", "gonna", "got", "had", "has", "have", "here", "how", "into", "its",
+    "just", "know", "let", "like", "look", "make", "more", "most", "much", "need", "not", "now",
+    "okay", "one", "only", "other", "our", "out", "over", "really", "right", "same", "see", "should",
+    "some", "something", "than", "that", "the", "their", "them", "then", "there", "these", "they",
+    "thing", "things", "think", "this", "those", "through", "too", "two", "use", "very", "want",
+    "was", "way", "well", "were", "what", "when", "where", "which", "while", "who", "why", "will",
+    "with", "would", "yeah", "you", "your",
+];
+
+/// Tuning for lexical-cohesion segmentation
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentationOptions {
+    /// Tokens per pseudo-sentence
+    pub sequence_tokens: usize,
+    /// Pseudo-sentences compared on each side of a candidate boundary
+    pub block_size: usize,
+    /// Chapters shorter than this are merged into their neighbours
+    pub min_chapter_secs: f64,
+}
+
+impl Default for SegmentationOptions {
+    fn default() -> Self {
+        Self {
+            sequence_tokens: 20,
+            block_size: 6,
+            min_chapter_secs: 60.0,
+        }
+    }
+}
+
+/// Creator chapters listed in a video description
+///
+/// Follows YouTube's rules: one timestamp per line, the first at 0:00, at
+/// least three, in ascending order. Anything else yields no chapters.
+pub fn parse_description_chapters(description: &str, duration: Option<f64>) -> Vec<Chapter> {
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    let line_re = LINE.get_or_init(|| {
+        Regex::new(r"^\s*(?:[-*•▶►]\s*)?[(\[]?((?:\d{1,2}:)?\d{1,2}:\d{2})[)\]]?\s*(?:[-–—:|.]\s*)?(.+?)\s*$").unwrap()
+    });
+
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for line in description.lines() {
+        let Some(caps) = line_re.captures(line) else { continue };
+        let Some(start) = parse_timestamp(&caps[1]) else { continue };
+        if chapters.last().is_some_and(|last| start <= last.start) {
+            return Vec::new();
+        }
+        chapters.push(Chapter { title: caps[2].to_string(), start, end: None });
+    }
+
+    if chapters.len() < MIN_DESCRIPTION_CHAPTERS || chapters[0].start != 0.0 {
+        return Vec::new();
+    }
+
+    close_chapters(&mut chapters, duration);
+    chapters
+}
+
+/// Split a transcript into topics where its vocabulary shifts (TextTiling)
+///
+/// The transcript is cut into pseudo-sentences of a fixed token count, and
+/// the similarity of the blocks either side of each gap is compared. Gaps in
+/// deep valleys of similarity become boundaries, snapped to the start of the
+/// caption that contains them. Titles are the chapter's most frequent terms.
+pub fn segment_topics(segments: &[TranscriptSegment], options: SegmentationOptions) -> Vec<Chapter> {
+    let Some(last) = segments.last() else { return Vec::new() };
+    let end = last.start + last.duration;
+
+    // Pseudo-sentences, each remembering the caption its first token came from
+    let mut sequences: Vec<(usize, Vec<String>)> = Vec::new();
+    for (index, segment) in segments.iter().enumerate() {
+        for token in tokenize(&segment.text) {
+            match sequences.last_mut() {
+                Some((_, tokens)) if tokens.len() < options.sequence_tokens => tokens.push(token),
+                _ => sequences.push((index, vec![token])),
+            }
+        }
+    }
+
+    let k = options.block_size.max(1);
+    let mut boundaries: Vec<f64> = Vec::new();
+    if sequences.len() > 2 * k {
+        // Similarity at the gap before each sequence
+        let gaps: Vec<usize> = (k..=sequences.len() - k).collect();
+        let scores: Vec<f64> = gaps
+            .iter()
+            .map(|&gap| cosine(&term_counts(&sequences[gap - k..gap]), &term_counts(&sequences[gap..gap + k])))
+            .collect();
+        let depths = depth_scores(&scores);
+
+        let mean = depths.iter().sum::<f64>() / depths.len() as f64;
+        let variance = depths.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / depths.len() as f64;
+        let cutoff = mean - variance.sqrt() / 2.0;
+
+        // Deepest valleys first, skipping any too close to an accepted boundary
+        let mut candidates: Vec<(f64, f64)> = (0..depths.len())
+            .filter(|&i| depths[i] > cutoff && depths[i] > 0.0)
+            .filter(|&i| (i == 0 || depths[i] >= depths[i - 1]) && (i + 1 == depths.len() || depths[i] >= depths[i + 1]))
+            .map(|i| (depths[i], segments[sequences[gaps[i]].0].start))
+            .collect();
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (_, t) in candidates {
+            let fits = t >= options.min_chapter_secs
+                && end - t >= options.min_chapter_secs
+                && boundaries.iter().all(|b| (b - t).abs() >= options.min_chapter_secs);
+            if fits {
+                boundaries.push(t);
+            }
+        }
+        boundaries.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    let mut chapters: Vec<Chapter> = std::iter::once(0.0)
+        .chain(boundaries)
+        .map(|start| Chapter { title: String::new(), start, end: None })
+        .collect();
+    close_chapters(&mut chapters, Some(end));
+
+    for (i, chapter) in chapters.iter_mut().enumerate() {
+        let text: Vec<&str> = chapter_segments(segments, chapter).iter().map(|s| s.text.as_str()).collect();
+        chapter.title = keyword_title(&text.join(" ")).unwrap_or_else(|| format!("Part {}", i + 1));
+    }
+    chapters
+}
+
+/// The captions that start inside a chapter
+pub fn chapter_segments<'a>(segments: &'a [TranscriptSegment], chapter: &Chapter) -> &'a [TranscriptSegment] {
+    let from = segments.partition_point(|s| s.start < chapter.start);
+    let to = match chapter.end {
+        Some(end) => segments.partition_point(|s| s.start < end),
+        None => segments.len(),
+    };
+    &segments[from..to.max(from)]
+}
+
+// Helper functions
+
+/// Seconds for `H:MM:SS` or `M:SS`
+fn parse_timestamp(text: &str) -> Option<f64> {
+    text.split(':')
+        .try_fold(0u64, |total, part| part.parse::<u64>().ok().map(|n| total * 60 + n))
+        .map(|secs| secs as f64)
+}
+
+/// End each chapter where the next begins, and the last at `duration`
+fn close_chapters(chapters: &mut [Chapter], duration: Option<f64>) {
+    let starts: Vec<f64> = chapters.iter().skip(1).map(|c| c.start).collect();
+    for (chapter, next) in chapters.iter_mut().zip(starts.into_iter().map(Some).chain(std::iter::once(duration))) {
+        chapter.end = next.filter(|end| *end >= chapter.start);
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| word.chars().count() > 2 && !word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+fn term_counts(sequences: &[(usize, Vec<String>)]) -> HashMap<&str, f64> {
+    let mut counts = HashMap::new();
+    for token in sequences.iter().flat_map(|(_, tokens)| tokens) {
+        *counts.entry(token.as_str()).or_insert(0.0) += 1.0;
+    }
+    counts
+}
+
+fn cosine(a: &HashMap<&str, f64>, b: &HashMap<&str, f64>) -> f64 {
+    let dot: f64 = a.iter().filter_map(|(term, x)| b.get(term).map(|y| x * y)).sum();
+    let norm = |v: &HashMap<&str, f64>| v.values().map(|x| x * x).sum::<f64>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 { 0.0 } else { dot / denominator }
+}
+
+/// How far each score sits below the peaks on either side of it
+fn depth_scores(scores: &[f64]) -> Vec<f64> {
+    (0..scores.len())
+        .map(|i| {
+            let mut left = scores[i];
+            for &s in scores[..i].iter().rev() {
+                if s < left { break; }
+                left = s;
+            }
+            let mut right = scores[i];
+            for &s in &scores[i + 1..] {
+                if s < right { break; }
+                right = s;
+            }
+            (left - scores[i]) + (right - scores[i])
+        })
+        .collect()
+}
+
+/// The three most frequent terms, most frequent first
+fn keyword_title(text: &str) -> Option<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for token in tokenize(text) {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+    let mut terms: Vec<(String, usize)> = counts.into_iter().collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if terms.is_empty() {
+        return None;
+    }
+
+    let title = terms.into_iter().take(3).map(|(term, _)| term).collect::<Vec<_>>().join(", ");
+    let mut chars = title.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_description_chapters() {
+        let description = "A quick tour of \"ownership\" and borrowing.\n\n0:00 Intro\n1:30 Moves\n5:00 Borrowing";
+        let chapters = parse_description_chapters(description, Some(754.0));
+        let summary: Vec<(&str, f64, Option<f64>)> =
+            chapters.iter().map(|c| (c.title.as_str(), c.start, c.end)).collect();
+        assert_eq!(
+            summary,
+            vec![("Intro", 0.0, Some(90.0)), ("Moves", 90.0, Some(300.0)), ("Borrowing", 300.0, Some(754.0))]
+        );
+
+        let chapters = parse_description_chapters("(00:00) Start\n- 12:05 - Middle\n1:02:03 | End", None);
+        assert_eq!(chapters.iter().map(|c| c.start).collect::<Vec<_>>(), vec![0.0, 725.0, 3723.0]);
+        assert_eq!(chapters[1].title, "Middle");
+        assert_eq!(chapters[2].end, None);
+    }
+
+    #[test]
+    fn test_description_chapters_follow_youtube_rules() {
+        // Must start at 0:00
+        assert!(parse_description_chapters("0:10 A\n1:00 B\n2:00 C", None).is_empty());
+        // Needs three
+        assert!(parse_description_chapters("0:00 A\n1:00 B", None).is_empty());
+        // Must ascend
+        assert!(parse_description_chapters("0:00 A\n2:00 B\n1:00 C", None).is_empty());
+    }
+
+    fn topic_transcript() -> Vec<TranscriptSegment> {
+        let topics = [
+            "ownership moves the value and the previous owner drops out of scope heap string",
+            "borrowing takes a reference shared immutable mutable lifetime checker aliasing",
+            "traits describe behaviour generic functions bound impl blocks dispatch dynamic",
+        ];
+        // Four minutes per topic, a caption every five seconds
+        topics
+            .iter()
+            .enumerate()
+            .flat_map(|(topic, text)| {
+                (0..48).map(move |i| TranscriptSegment {
+                    text: text.to_string(),
+                    start: (topic * 48 + i) as f64 * 5.0,
+                    duration: 5.0,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_segment_topics_finds_vocabulary_shifts() {
+        let transcript = topic_transcript();
+        let chapters = segment_topics(&transcript, SegmentationOptions::default());
+
+        let bounds: Vec<(f64, Option<f64>)> = chapters.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(bounds.len(), 3, "{:?}", bounds);
+        assert_eq!(bounds[0].0, 0.0);
+        assert!((bounds[1].0 - 240.0).abs() <= 10.0, "{:?}", bounds);
+        assert!((bounds[2].0 - 480.0).abs() <= 10.0, "{:?}", bounds);
+        assert_eq!(bounds[2].1, Some(720.0));
+
+        // Every caption in a topic repeats, so ties fall back to alphabetical order
+        assert_eq!(chapters[1].title, "Aliasing, borrowing, checker");
+        assert_eq!(chapter_segments(&transcript, &chapters[0]).len() as f64 * 5.0, chapters[1].start);
+    }
+
+    #[test]
+    fn test_segment_topics_short_transcript() {
+        let transcript = &topic_transcript()[..3];
+        let chapters = segment_topics(transcript, SegmentationOptions::default());
+        assert_eq!(chapters.len(), 1);
+        assert_eq!((chapters[0].start, chapters[0].end), (0.0, Some(15.0)));
+        assert!(segment_topics(&[], SegmentationOptions::default()).is_empty());
+    }
+}
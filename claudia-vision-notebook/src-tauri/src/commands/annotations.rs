@@ -0,0 +1,281 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::history::{create_notebook_entry, delete_notebook_entries};
+use crate::transcript::subtitles::format_clock;
+
+/// A note the user attached to a moment in a video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptAnnotation {
+    pub id: i64,
+    pub history_id: i64,
+    /// Seconds into the video
+    pub t: f64,
+    pub text: String,
+    pub tags: Vec<String>,
+    /// Frame the note refers to, usually one from the manifest
+    pub frame_path: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Attach a note to the moment `t` of a video
+#[command]
+pub async fn add_annotation(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    t: f64,
+    text: String,
+    tags: Option<Vec<String>>,
+    frame_path: Option<String>,
+) -> Result<TranscriptAnnotation, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let annotation_id = insert_annotation(&conn, history_id, t, &text, &tags.unwrap_or_default(), frame_path.as_deref())?;
+    load_annotation(&conn, annotation_id)
+}
+
+/// Replace the time, text, tags and frame of a note
+#[command]
+pub async fn update_annotation(
+    db: State<'_, AgentDb>,
+    annotation_id: i64,
+    t: f64,
+    text: String,
+    tags: Option<Vec<String>>,
+    frame_path: Option<String>,
+) -> Result<TranscriptAnnotation, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let (t, text, tags) = validate(t, &text, &tags.unwrap_or_default())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let updated = tx.execute(
+        "UPDATE transcript_annotations
+         SET t = ?1, text = ?2, tags = ?3, frame_path = ?4, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?5",
+        params![t, &text, &tags, &frame_path, annotation_id],
+    ).map_err(|e| format!("Failed to update annotation: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Annotation {} not found", annotation_id));
+    }
+
+    let annotation = load_annotation(&tx, annotation_id)?;
+    index_annotation(&tx, &annotation)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(annotation)
+}
+
+/// Delete a note and its notebook entry
+#[command]
+pub async fn delete_annotation(
+    db: State<'_, AgentDb>,
+    annotation_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM transcript_annotations WHERE id = ?1", params![annotation_id])
+        .map_err(|e| format!("Failed to delete annotation: {}", e))?;
+    delete_notebook_entries(&tx, "annotation", annotation_id)
+        .map_err(|e| format!("Failed to delete notebook entry: {}", e))?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Notes on a video in time order
+#[command]
+pub async fn get_annotations(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+) -> Result<Vec<TranscriptAnnotation>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_annotations(&conn, history_id)
+}
+
+// Helper functions
+
+fn insert_annotation(
+    conn: &Connection,
+    history_id: i64,
+    t: f64,
+    text: &str,
+    tags: &[String],
+    frame_path: Option<&str>,
+) -> Result<i64, String> {
+    let (t, text, tags) = validate(t, text, tags)?;
+
+    // The note and its notebook entry are saved together or not at all
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO transcript_annotations (history_id, t, text, tags, frame_path)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![history_id, t, &text, &tags, frame_path],
+    ).map_err(|e| format!("Failed to save annotation: {}", e))?;
+    let annotation_id = tx.last_insert_rowid();
+
+    index_annotation(&tx, &load_annotation(&tx, annotation_id)?)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(annotation_id)
+}
+
+/// Check a note and return its time, trimmed text and tags as a JSON array
+fn validate(t: f64, text: &str, tags: &[String]) -> Result<(f64, String, Option<String>), String> {
+    if !t.is_finite() || t < 0.0 {
+        return Err(format!("Invalid annotation time: {}", t));
+    }
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Annotation text is empty".to_string());
+    }
+
+    let tags: Vec<&str> = tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()).collect();
+    let tags = if tags.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&tags).map_err(|e| e.to_string())?)
+    };
+    Ok((t, text.to_string(), tags))
+}
+
+fn load_annotation(conn: &Connection, annotation_id: i64) -> Result<TranscriptAnnotation, String> {
+    conn.query_row(
+        "SELECT id, history_id, t, text, tags, frame_path, created_at, updated_at
+         FROM transcript_annotations WHERE id = ?1",
+        params![annotation_id],
+        annotation_from_row,
+    ).map_err(|e| format!("Failed to get annotation: {}", e))
+}
+
+/// Notes on a video in time order
+pub(crate) fn load_annotations(conn: &Connection, history_id: i64) -> Result<Vec<TranscriptAnnotation>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, history_id, t, text, tags, frame_path, created_at, updated_at
+         FROM transcript_annotations WHERE history_id = ?1
+         ORDER BY t, id"
+    ).map_err(|e| e.to_string())?;
+
+    let annotations = stmt.query_map(params![history_id], annotation_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to get annotations: {}", e))?;
+    Ok(annotations)
+}
+
+/// Delete every note on a video with its notebook entries
+pub(crate) fn delete_annotations(conn: &Connection, history_id: i64) -> Result<(), String> {
+    for annotation in load_annotations(conn, history_id)? {
+        delete_notebook_entries(conn, "annotation", annotation.id)
+            .map_err(|e| format!("Failed to delete notebook entry: {}", e))?;
+    }
+    conn.execute("DELETE FROM transcript_annotations WHERE history_id = ?1", params![history_id])
+        .map_err(|e| format!("Failed to delete annotations: {}", e))?;
+    Ok(())
+}
+
+fn annotation_from_row(row: &rusqlite::Row) -> rusqlite::Result<TranscriptAnnotation> {
+    let tags = match row.get::<_, Option<String>>(4)? {
+        Some(json) => serde_json::from_str(&json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        None => Vec::new(),
+    };
+    Ok(TranscriptAnnotation {
+        id: row.get(0)?,
+        history_id: row.get(1)?,
+        t: row.get(2)?,
+        text: row.get(3)?,
+        tags,
+        frame_path: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// Replace a note's notebook entry so it is found by `search_notebook`
+///
+/// The entry is titled with the video and the moment, e.g. "Intro to Rust @ 12:05".
+fn index_annotation(conn: &Connection, annotation: &TranscriptAnnotation) -> Result<(), String> {
+    let video_title: String = conn.query_row(
+        "SELECT title FROM transcript_history WHERE id = ?1",
+        params![annotation.history_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to get video for annotation: {}", e))?;
+
+    delete_notebook_entries(conn, "annotation", annotation.id)
+        .map_err(|e| format!("Failed to replace notebook entry: {}", e))?;
+    let tags = annotation.tags.join(",");
+    create_notebook_entry(
+        conn,
+        "annotation",
+        Some(annotation.id),
+        &format!("{} @ {}", video_title, format_clock(annotation.t)),
+        Some(&annotation.text),
+        (!tags.is_empty()).then_some(tags.as_str()),
+    ).map_err(|e| format!("Failed to create notebook entry: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+    use crate::commands::vision_notebook::load_transcript_details;
+
+    fn search(conn: &Connection, query: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(
+            "SELECT title FROM search_index WHERE search_index MATCH ?1 AND entry_type = 'annotation'"
+        ).unwrap();
+        stmt.query_map(params![query], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_annotations_are_listed_in_time_order_with_details() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Video abc", "Channel");
+        insert_annotation(&conn, history_id, 725.0, "Second point", &[], None).unwrap();
+        insert_annotation(&conn, history_id, 12.5, " The key diagram ", &["diagram".into(), " ".into()], Some("frames/frame_000006.jpg")).unwrap();
+
+        let details = load_transcript_details(&conn, history_id).unwrap();
+        let texts: Vec<&str> = details.annotations.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(texts, vec!["The key diagram", "Second point"]);
+        assert_eq!(details.annotations[0].tags, vec!["diagram"]);
+        assert_eq!(details.annotations[0].frame_path.as_deref(), Some("frames/frame_000006.jpg"));
+        assert!(details.annotations[1].tags.is_empty());
+
+        assert!(insert_annotation(&conn, history_id, -1.0, "Before the start", &[], None).is_err());
+        assert!(insert_annotation(&conn, history_id, 1.0, "  ", &[], None).is_err());
+
+        // A note on an unknown video is not saved at all
+        assert!(insert_annotation(&conn, history_id + 1, 1.0, "Orphan", &[], None).is_err());
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM transcript_annotations", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_annotation_tags_round_trip() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Video abc", "Channel");
+        let annotation_id = insert_annotation(&conn, history_id, 30.0, "Tagged", &["rust, async".into(), "exam".into()], None).unwrap();
+
+        assert_eq!(load_annotation(&conn, annotation_id).unwrap().tags, vec!["rust, async", "exam"]);
+        let details = load_transcript_details(&conn, history_id).unwrap();
+        assert_eq!(details.annotations[0].tags, vec!["rust, async", "exam"]);
+    }
+
+    #[test]
+    fn test_annotations_are_searchable_until_deleted() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Video abc", "Channel");
+        let annotation_id = insert_annotation(&conn, history_id, 725.0, "Borrow checker rejects this", &[], None).unwrap();
+        assert_eq!(search(&conn, "borrow"), vec!["Video abc @ 12:05"]);
+
+        conn.execute("UPDATE transcript_annotations SET text = 'Lifetimes explained' WHERE id = ?1", params![annotation_id]).unwrap();
+        index_annotation(&conn, &load_annotation(&conn, annotation_id).unwrap()).unwrap();
+        assert!(search(&conn, "borrow").is_empty());
+        assert_eq!(search(&conn, "lifetimes").len(), 1);
+
+        delete_annotations(&conn, history_id).unwrap();
+        assert!(search(&conn, "lifetimes").is_empty());
+        assert!(load_annotations(&conn, history_id).unwrap().is_empty());
+    }
+}
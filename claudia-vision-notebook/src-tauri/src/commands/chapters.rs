@@ -0,0 +1,197 @@
+use log::warn;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+use tauri::{command, State};
+use crate::chapters::{chapter_segments, segment_topics, SegmentationOptions};
+use crate::commands::agents::AgentDb;
+use crate::commands::vision_notebook::{call_claude_cli, load_transcript_details, TranscriptSegment};
+use crate::manifest::{Chapter, Manifest};
+use crate::structured_output::extract_json_array;
+use crate::transcript::subtitles::format_clock;
+
+/// Transcript text shown to the model for each chapter it titles
+const TITLE_EXCERPT_CHARS: usize = 1500;
+
+/// Chapters of a stored transcript and how they were found
+#[derive(Debug, Serialize)]
+pub struct VideoChapters {
+    pub chapters: Vec<Chapter>,
+    /// "description", "segmentation" or "llm"; None before detection
+    pub source: Option<String>,
+}
+
+/// Get the chapters stored for a history entry
+#[command]
+pub async fn get_video_chapters(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+) -> Result<VideoChapters, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let details = load_transcript_details(&conn, history_id)?;
+    Ok(VideoChapters {
+        chapters: details.chapters,
+        source: details.chapter_source,
+    })
+}
+
+/// Detect chapters for a stored transcript and save them with it
+///
+/// Creator chapters from the video description are kept as they are.
+/// Otherwise the transcript is segmented by topic, and with `use_llm` the
+/// segments are titled by Claude instead of by their keywords.
+#[command]
+pub async fn detect_video_chapters(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    use_llm: Option<bool>,
+) -> Result<VideoChapters, String> {
+    let details = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_transcript_details(&conn, history_id)?
+    };
+
+    if details.chapter_source.as_deref() == Some("description") && !details.chapters.is_empty() {
+        return Ok(VideoChapters {
+            chapters: details.chapters,
+            source: details.chapter_source,
+        });
+    }
+
+    let transcript = details.cleaned_transcript.as_ref().unwrap_or(&details.transcript);
+    let mut chapters = segment_topics(transcript, SegmentationOptions::default());
+    let mut source = "segmentation";
+
+    if use_llm.unwrap_or(false) && chapters.len() > 1 {
+        let output = call_claude_cli(&title_prompt(transcript, &chapters), None).await?;
+        match parse_titles(&output, chapters.len()) {
+            Ok(titles) => {
+                for (chapter, title) in chapters.iter_mut().zip(titles) {
+                    chapter.title = title;
+                }
+                source = "llm";
+            }
+            Err(e) => warn!("Keeping keyword chapter titles: {}", e),
+        }
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    store_chapters(&conn, history_id, &chapters, source)?;
+
+    // The manifest carries chapters for tools reading it directly
+    if let Some(manifest_path) = details.manifest_path.as_deref().map(Path::new) {
+        if manifest_path.exists() {
+            let mut manifest = Manifest::load(manifest_path)?;
+            manifest.chapters = chapters.clone();
+            manifest.save(manifest_path)?;
+        }
+    }
+
+    Ok(VideoChapters {
+        chapters,
+        source: Some(source.to_string()),
+    })
+}
+
+// Helper functions
+
+/// Save chapters on a history entry; an empty list clears them
+pub(crate) fn store_chapters(
+    conn: &Connection,
+    history_id: i64,
+    chapters: &[Chapter],
+    source: &str,
+) -> Result<(), String> {
+    let (chapters_json, source) = if chapters.is_empty() {
+        (None, None)
+    } else {
+        let json = serde_json::to_string(chapters)
+            .map_err(|e| format!("Failed to serialize chapters: {}", e))?;
+        (Some(json), Some(source))
+    };
+
+    conn.execute(
+        "UPDATE transcript_history SET chapters = ?1, chapter_source = ?2 WHERE id = ?3",
+        params![chapters_json, source, history_id],
+    ).map_err(|e| format!("Failed to save chapters: {}", e))?;
+
+    Ok(())
+}
+
+fn title_prompt(transcript: &[TranscriptSegment], chapters: &[Chapter]) -> String {
+    let sections: Vec<String> = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| {
+            let text: String = chapter_segments(transcript, chapter)
+                .iter()
+                .map(|s| s.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(TITLE_EXCERPT_CHARS)
+                .collect();
+            format!("Chapter {} [{}]:\n{}", i + 1, format_clock(chapter.start), text)
+        })
+        .collect();
+
+    format!(
+        "The following are the opening lines of {} consecutive chapters of one video.
+        Give each chapter a short title of at most six words.
+        Respond with only a JSON array of {} strings, one title per chapter in order.
+
+        {}",
+        chapters.len(),
+        chapters.len(),
+        sections.join("\n\n")
+    )
+}
+
+fn parse_titles(output: &str, expected: usize) -> Result<Vec<String>, String> {
+    let titles: Vec<String> = serde_json::from_str(extract_json_array(output)?)
+        .map_err(|e| format!("Invalid title JSON: {}", e))?;
+
+    if titles.len() != expected {
+        return Err(format!("Expected {} titles, got {}", expected, titles.len()));
+    }
+    if titles.iter().any(|title| title.trim().is_empty()) {
+        return Err("A chapter title is empty".to_string());
+    }
+
+    Ok(titles.into_iter().map(|title| title.trim().to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    #[test]
+    fn test_parse_titles() {
+        let output = "Here you go:\n```json\n[\" Ownership basics\", \"Borrowing\"]\n```";
+        assert_eq!(parse_titles(output, 2).unwrap(), vec!["Ownership basics", "Borrowing"]);
+        assert_eq!(parse_titles(output, 3).unwrap_err(), "Expected 3 titles, got 2");
+        assert!(parse_titles("[\"Intro\", \"\"]", 2).is_err());
+    }
+
+    #[test]
+    fn test_store_chapters() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abcDEF12345", "Ownership", "Fixture Channel");
+
+        let chapters = vec![
+            Chapter { title: "Intro".to_string(), start: 0.0, end: Some(90.0) },
+            Chapter { title: "Moves".to_string(), start: 90.0, end: None },
+        ];
+        store_chapters(&conn, history_id, &chapters, "description").unwrap();
+
+        let details = load_transcript_details(&conn, history_id).unwrap();
+        assert_eq!(details.chapter_source.as_deref(), Some("description"));
+        assert_eq!(details.chapters.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), vec!["Intro", "Moves"]);
+
+        store_chapters(&conn, history_id, &[], "segmentation").unwrap();
+        let details = load_transcript_details(&conn, history_id).unwrap();
+        assert!(details.chapters.is_empty());
+        assert_eq!(details.chapter_source, None);
+    }
+}
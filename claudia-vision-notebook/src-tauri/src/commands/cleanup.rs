@@ -0,0 +1,86 @@
+use log::warn;
+use rusqlite::params;
+use std::path::Path;
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::history::delete_notebook_entries;
+use crate::commands::search::index_segments;
+use crate::commands::vision_notebook::{
+    call_claude_cli, index_transcript_entry, load_transcript_details, TranscriptSegment, VideoInfo,
+};
+use crate::manifest::Manifest;
+use crate::transcript::cleanup::{apply_punctuation, caption_words, punctuation_prompt, resegment, CleanupOptions, Segmentation};
+
+/// Words sent to the model per punctuation request
+const PUNCTUATION_CHUNK_WORDS: usize = 300;
+
+/// Clean up a video's captions and use the result for the manifest, summaries and search
+///
+/// Rolling-caption overlaps and repeated phrases are removed and the words are
+/// regrouped into sentences (the default) or paragraphs. With
+/// `restore_punctuation` the model punctuates the text first; chunks it
+/// changes beyond punctuation keep their original words. The raw transcript
+/// is kept in `transcript` and in the manifest's `raw_transcript`.
+#[command]
+pub async fn clean_transcript(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    segmentation: Option<Segmentation>,
+    restore_punctuation: Option<bool>,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let details = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_transcript_details(&conn, history_id)?
+    };
+
+    let options = CleanupOptions {
+        segmentation: segmentation.unwrap_or(Segmentation::Sentences),
+        ..Default::default()
+    };
+    let mut words = caption_words(&details.transcript, options.max_ngram);
+
+    if restore_punctuation.unwrap_or(false) {
+        for chunk in words.chunks_mut(PUNCTUATION_CHUNK_WORDS) {
+            let response = call_claude_cli(&punctuation_prompt(chunk), None).await?;
+            if !apply_punctuation(chunk, &response) {
+                warn!("Punctuation for history {} changed the words; keeping the captions", history_id);
+            }
+        }
+    }
+
+    let cleaned = resegment(&words, &options);
+    if cleaned.is_empty() {
+        return Err("Transcript has no words to clean".to_string());
+    }
+
+    if let Some(manifest_path) = details.manifest_path.as_deref().map(Path::new) {
+        if manifest_path.exists() {
+            let mut manifest = Manifest::load(manifest_path)?;
+            manifest.apply_cleaned_transcript(&cleaned);
+            manifest.save(manifest_path)?;
+        }
+    }
+
+    let cleaned_json = serde_json::to_string(&cleaned)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE transcript_history SET cleaned_transcript = ?1 WHERE id = ?2",
+        params![&cleaned_json, history_id],
+    ).map_err(|e| format!("Failed to save cleaned transcript: {}", e))?;
+    index_segments(&conn, history_id, &cleaned)?;
+
+    // The notebook preview shows the cleaned text too
+    delete_notebook_entries(&conn, "transcript", history_id)
+        .map_err(|e| format!("Failed to replace notebook entry: {}", e))?;
+    let video_info = VideoInfo {
+        video_id: details.video_id,
+        title: details.title,
+        channel: details.channel,
+        duration: details.duration,
+        thumbnail_url: details.thumbnail_url,
+    };
+    index_transcript_entry(&conn, history_id, &video_info, &cleaned)?;
+
+    Ok(cleaned)
+}
@@ -0,0 +1,285 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::vision_notebook::{
+    download_video, ensure_history_exists, load_transcript_details, TranscriptHistoryDetails,
+};
+use crate::media::{cut_clip, ClipFormat};
+use crate::progress::{PipelineStage, ProgressReporter};
+use crate::transcript::subtitles::{render_subtitles, slice_segments, CueOptions, SubtitleFormat};
+
+/// A titled range of a video to cut into a clip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipBookmark {
+    pub id: i64,
+    pub history_id: i64,
+    pub title: String,
+    /// Seconds into the video
+    pub start: f64,
+    pub end: f64,
+    pub created_at: String,
+}
+
+/// Files written by `export_clip`
+#[derive(Debug, Serialize)]
+pub struct ClipExport {
+    pub clip_id: i64,
+    pub video_path: String,
+    /// SRT of the transcript during the clip; absent when nothing is said
+    pub subtitle_path: Option<String>,
+}
+
+/// Bookmark `[start, end]` of a video under a title
+#[command]
+pub async fn add_clip_bookmark(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    title: String,
+    start: f64,
+    end: f64,
+) -> Result<ClipBookmark, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let clip_id = insert_clip(&conn, history_id, &title, start, end)?;
+    load_clip(&conn, clip_id)
+}
+
+/// Change the title or range of a clip bookmark
+#[command]
+pub async fn update_clip_bookmark(
+    db: State<'_, AgentDb>,
+    clip_id: i64,
+    title: String,
+    start: f64,
+    end: f64,
+) -> Result<ClipBookmark, String> {
+    let title = validate(&title, start, end)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let updated = conn.execute(
+        "UPDATE clip_bookmarks SET title = ?1, start = ?2, end = ?3 WHERE id = ?4",
+        params![&title, start, end, clip_id],
+    ).map_err(|e| format!("Failed to update clip: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Clip {} not found", clip_id));
+    }
+    load_clip(&conn, clip_id)
+}
+
+/// Delete a clip bookmark; exported files are left alone
+#[command]
+pub async fn delete_clip_bookmark(
+    db: State<'_, AgentDb>,
+    clip_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM clip_bookmarks WHERE id = ?1", params![clip_id])
+        .map_err(|e| format!("Failed to delete clip: {}", e))?;
+    Ok(())
+}
+
+/// Clip bookmarks of a video in time order
+#[command]
+pub async fn get_clip_bookmarks(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+) -> Result<Vec<ClipBookmark>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_clips(&conn, history_id)
+}
+
+/// Cut a bookmarked clip into `output_dir` as `<video_id>_clip<id>.mp4` or `.gif`
+///
+/// The clip is cut from the video `process_youtube_video` downloaded, which is
+/// downloaded now if it was skipped; local videos are cut from the original
+/// file. The transcript during the clip is written next to it as an SRT and,
+/// for MP4, also added as a subtitle track.
+#[command]
+pub async fn export_clip(
+    app: AppHandle,
+    clip_id: i64,
+    format: ClipFormat,
+    output_dir: String,
+) -> Result<ClipExport, String> {
+    let (clip, details) = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let clip = load_clip(&conn, clip_id)?;
+        let details = load_transcript_details(&conn, clip.history_id)?;
+        (clip, details)
+    };
+
+    let output_dir = PathBuf::from(&output_dir);
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    let source = source_video(&app, &details).await?;
+
+    let stem = format!("{}_clip{}", details.video_id, clip.id);
+    let transcript = details.cleaned_transcript.unwrap_or(details.transcript);
+    let slice = slice_segments(&transcript, clip.start, clip.end);
+    let subtitle_path = if slice.is_empty() {
+        None
+    } else {
+        let path = output_dir.join(format!("{}.srt", stem));
+        std::fs::write(&path, render_subtitles(&slice, SubtitleFormat::Srt, &CueOptions::default()))
+            .map_err(|e| format!("Failed to write subtitles: {}", e))?;
+        Some(path)
+    };
+
+    let video_path = output_dir.join(format!("{}.{}", stem, format.extension()));
+    cut_clip(&source, &video_path, clip.start, clip.end, format, subtitle_path.as_deref()).await?;
+
+    Ok(ClipExport {
+        clip_id: clip.id,
+        video_path: video_path.to_string_lossy().to_string(),
+        subtitle_path: subtitle_path.map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+// Helper functions
+
+fn insert_clip(conn: &Connection, history_id: i64, title: &str, start: f64, end: f64) -> Result<i64, String> {
+    let title = validate(title, start, end)?;
+    ensure_history_exists(conn, history_id)?;
+    conn.execute(
+        "INSERT INTO clip_bookmarks (history_id, title, start, end) VALUES (?1, ?2, ?3, ?4)",
+        params![history_id, &title, start, end],
+    ).map_err(|e| format!("Failed to save clip: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Check a clip's range and return its trimmed title
+fn validate(title: &str, start: f64, end: f64) -> Result<String, String> {
+    if !start.is_finite() || !end.is_finite() || start < 0.0 || end <= start {
+        return Err(format!("Invalid clip range: {} to {}", start, end));
+    }
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Clip title is empty".to_string());
+    }
+    Ok(title.to_string())
+}
+
+fn load_clip(conn: &Connection, clip_id: i64) -> Result<ClipBookmark, String> {
+    conn.query_row(
+        "SELECT id, history_id, title, start, end, created_at FROM clip_bookmarks WHERE id = ?1",
+        params![clip_id],
+        clip_from_row,
+    ).map_err(|e| format!("Failed to get clip: {}", e))
+}
+
+/// Clip bookmarks of a video in time order
+pub(crate) fn load_clips(conn: &Connection, history_id: i64) -> Result<Vec<ClipBookmark>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, history_id, title, start, end, created_at FROM clip_bookmarks
+         WHERE history_id = ?1
+         ORDER BY start, id"
+    ).map_err(|e| e.to_string())?;
+
+    let clips = stmt.query_map(params![history_id], clip_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to get clips: {}", e))?;
+    Ok(clips)
+}
+
+fn clip_from_row(row: &rusqlite::Row) -> rusqlite::Result<ClipBookmark> {
+    Ok(ClipBookmark {
+        id: row.get(0)?,
+        history_id: row.get(1)?,
+        title: row.get(2)?,
+        start: row.get(3)?,
+        end: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+/// The video file a clip is cut from
+async fn source_video(app: &AppHandle, details: &TranscriptHistoryDetails) -> Result<PathBuf, String> {
+    if let Some(path) = local_video_file(details)? {
+        return Ok(path);
+    }
+
+    let video_dir = details.manifest_path.as_deref()
+        .and_then(|path| Path::new(path).parent())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "This transcript has no video to cut clips from".to_string())?;
+    // Where `download_video` writes
+    let downloaded = video_dir.join(format!("{}.mp4", details.video_id));
+    if downloaded.exists() {
+        return Ok(downloaded);
+    }
+    if details.source_type != "youtube" {
+        return Err("This transcript has no video to cut clips from".to_string());
+    }
+
+    std::fs::create_dir_all(&video_dir)
+        .map_err(|e| format!("Failed to create video directory: {}", e))?;
+    let mut reporter = ProgressReporter::new(app, &details.video_id);
+    reporter.stage(PipelineStage::Downloading);
+    let path = download_video(&details.video_id, &video_dir, |update| reporter.download(&update)).await?;
+    reporter.stage(PipelineStage::Done);
+    Ok(path)
+}
+
+/// The file behind a processed local video; None for other sources
+///
+/// Subtitle imports also store a `file://` URL, but it points at the
+/// subtitles rather than a video.
+fn local_video_file(details: &TranscriptHistoryDetails) -> Result<Option<PathBuf>, String> {
+    if details.source_type != "local_video" {
+        return Ok(None);
+    }
+    let path = details.video_url.strip_prefix("file://")
+        .map(PathBuf::from)
+        .ok_or_else(|| "This transcript has no video to cut clips from".to_string())?;
+    if path.exists() {
+        Ok(Some(path))
+    } else {
+        Err(format!("Video file not found: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    #[test]
+    fn test_clips_are_validated_and_listed_in_order() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Talk", "Channel");
+
+        insert_clip(&conn, history_id, " Q&A ", 3000.0, 3120.5).unwrap();
+        insert_clip(&conn, history_id, "Opening joke", 5.0, 20.0).unwrap();
+        assert!(insert_clip(&conn, history_id + 1, "Orphan", 5.0, 20.0).unwrap_err().contains("not found"));
+        assert!(insert_clip(&conn, history_id, "Backwards", 20.0, 5.0).is_err());
+        assert!(insert_clip(&conn, history_id, "", 1.0, 2.0).is_err());
+
+        let clips = load_clips(&conn, history_id).unwrap();
+        let titles: Vec<&str> = clips.iter().map(|clip| clip.title.as_str()).collect();
+        assert_eq!(titles, vec!["Opening joke", "Q&A"]);
+        assert_eq!((clips[1].start, clips[1].end), (3000.0, 3120.5));
+    }
+
+    #[test]
+    fn test_only_local_videos_are_cut_from_their_file() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "local-abc", "Lecture", "Local import");
+        let video = tempfile::NamedTempFile::new().unwrap();
+        let set_source = |source_type: &str, url: &str| {
+            conn.execute(
+                "UPDATE transcript_history SET source_type = ?1, video_url = ?2 WHERE id = ?3",
+                params![source_type, url, history_id],
+            ).unwrap();
+            local_video_file(&load_transcript_details(&conn, history_id).unwrap())
+        };
+
+        let url = format!("file://{}", video.path().display());
+        assert_eq!(set_source("local_video", &url).unwrap().as_deref(), Some(video.path()));
+        assert!(set_source("local_video", "file:///missing/talk.mp4").unwrap_err().contains("not found"));
+        // An imported .srt is not a video
+        assert_eq!(set_source("subtitle_file", &url).unwrap(), None);
+    }
+}
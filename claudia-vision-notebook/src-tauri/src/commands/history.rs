@@ -439,7 +439,7 @@ fn update_project_access(conn: &rusqlite::Connection, project_path: &str) -> Sql
     Ok(())
 }
 
-fn create_notebook_entry(
+pub(crate) fn create_notebook_entry(
     conn: &rusqlite::Connection,
     entry_type: &str,
     entry_id: Option<i64>,
@@ -469,6 +469,25 @@ fn create_notebook_entry(
     Ok(())
 }
 
+/// Remove the notebook entries and search index rows that point at a record
+pub(crate) fn delete_notebook_entries(
+    conn: &rusqlite::Connection,
+    entry_type: &str,
+    entry_id: i64,
+) -> SqliteResult<()> {
+    conn.execute(
+        "DELETE FROM search_index WHERE entry_type = ?1 AND entry_id IN (
+             SELECT id FROM notebook_entries WHERE entry_type = ?1 AND entry_id = ?2
+         )",
+        params![entry_type, entry_id],
+    )?;
+    conn.execute(
+        "DELETE FROM notebook_entries WHERE entry_type = ?1 AND entry_id = ?2",
+        params![entry_type, entry_id],
+    )?;
+    Ok(())
+}
+
 /// Delete old sessions older than specified days
 #[command]
 pub async fn cleanup_old_sessions(
@@ -0,0 +1,157 @@
+use log::{info, warn};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::{command, AppHandle, Manager};
+use crate::commands::agents::AgentDb;
+use crate::commands::vision_notebook::process_youtube_video;
+use crate::transcript::playlist::{VideoCollection, VideoList, VideoListProvider, YouTubeBrowseProvider};
+
+/// What happened to one video of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchVideoStatus {
+    Processed,
+    /// Already in the transcript history
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchVideoResult {
+    pub video_id: String,
+    pub status: BatchVideoStatus,
+    pub title: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Per-video outcome of ingesting a playlist or channel
+#[derive(Debug, Serialize)]
+pub struct BatchIngestReport {
+    pub collection: VideoCollection,
+    pub title: Option<String>,
+    pub results: Vec<BatchVideoResult>,
+    pub processed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// List the videos of a playlist or channel URL without processing them
+#[command]
+pub async fn expand_youtube_collection(url: String) -> Result<VideoList, String> {
+    let collection = VideoCollection::parse(&url)
+        .ok_or_else(|| "Not a YouTube playlist or channel URL".to_string())?;
+    YouTubeBrowseProvider.list_videos(&collection).await
+}
+
+/// Process every video of a playlist or channel in order
+///
+/// Videos already in the transcript history are skipped, so re-running a
+/// batch picks up where a failed or interrupted run stopped. One failing
+/// video does not stop the rest.
+#[command]
+pub async fn ingest_youtube_collection(
+    app: AppHandle,
+    url: String,
+    project_dir: Option<String>,
+    fps: Option<f64>,
+    language: Option<String>,
+    scene_threshold: Option<f64>,
+) -> Result<BatchIngestReport, String> {
+    let collection = VideoCollection::parse(&url)
+        .ok_or_else(|| "Not a YouTube playlist or channel URL".to_string())?;
+    let list = YouTubeBrowseProvider.list_videos(&collection).await?;
+    info!("Ingesting {} videos from {:?}", list.video_ids.len(), collection);
+
+    let existing = {
+        let db = app.state::<AgentDb>();
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        existing_video_ids(&conn, &list.video_ids)?
+    };
+
+    let mut results = Vec::new();
+    for video_id in &list.video_ids {
+        if existing.contains(video_id) {
+            results.push(BatchVideoResult {
+                video_id: video_id.clone(),
+                status: BatchVideoStatus::Skipped,
+                title: None,
+                error: None,
+            });
+            continue;
+        }
+
+        let video_url = format!("https://www.youtube.com/watch?v={}", video_id);
+        let outcome = process_youtube_video(
+            app.clone(),
+            video_url,
+            project_dir.clone(),
+            fps,
+            language.clone(),
+            scene_threshold,
+        ).await;
+
+        results.push(match outcome {
+            Ok(result) => BatchVideoResult {
+                video_id: video_id.clone(),
+                status: BatchVideoStatus::Processed,
+                title: result.video_info.map(|info| info.title),
+                error: None,
+            },
+            Err(e) => {
+                warn!("Failed to ingest {}: {}", video_id, e);
+                BatchVideoResult {
+                    video_id: video_id.clone(),
+                    status: BatchVideoStatus::Failed,
+                    title: None,
+                    error: Some(e),
+                }
+            }
+        });
+    }
+
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    Ok(BatchIngestReport {
+        processed: count(BatchVideoStatus::Processed),
+        skipped: count(BatchVideoStatus::Skipped),
+        failed: count(BatchVideoStatus::Failed),
+        collection,
+        title: list.title,
+        results,
+    })
+}
+
+// Helper functions
+
+/// The subset of `video_ids` that already has a transcript history row
+fn existing_video_ids(conn: &Connection, video_ids: &[String]) -> Result<HashSet<String>, String> {
+    let mut stmt = conn.prepare("SELECT 1 FROM transcript_history WHERE video_id = ?1")
+        .map_err(|e| e.to_string())?;
+
+    let mut existing = HashSet::new();
+    for video_id in video_ids {
+        let found = stmt.query_row(params![video_id], |_| Ok(()))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if found.is_some() {
+            existing.insert(video_id.clone());
+        }
+    }
+    Ok(existing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    #[test]
+    fn test_existing_videos_are_skipped() {
+        let conn = open_test_db();
+        insert_test_video(&conn, "ghiJKL67890", "Borrowing", "Crab Academy");
+
+        let ids: Vec<String> = ["abcDEF12345", "ghiJKL67890", "mnoPQR13579"].iter().map(|s| s.to_string()).collect();
+        let existing = existing_video_ids(&conn, &ids).unwrap();
+        assert_eq!(existing, HashSet::from(["ghiJKL67890".to_string()]));
+    }
+}
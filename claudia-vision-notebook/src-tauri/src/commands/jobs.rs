@@ -0,0 +1,244 @@
+use log::{error, info, warn};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+use crate::commands::agents::AgentDb;
+use crate::commands::vision_notebook::{run_youtube_pipeline, YouTubeVideoProgress, YouTubeVideoRequest};
+use crate::jobs::{self, Job, JobStatus, DEFAULT_MAX_ATTEMPTS};
+use crate::transcript::TranscriptProviderState;
+
+/// Job kind for `process_youtube_video` run in the background
+pub const YOUTUBE_VIDEO_JOB: &str = "youtube_video";
+
+/// Event emitted with the job whenever its status or stage changes
+pub const JOB_UPDATED_EVENT: &str = "job-updated";
+
+/// How often the worker looks for retries that have become due
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Wakes the worker and signals cancellation to running jobs
+#[derive(Default)]
+pub struct JobQueueState {
+    wake: Notify,
+    running: Mutex<HashMap<i64, Arc<Notify>>>,
+}
+
+/// Queue a YouTube video to be processed in the background
+///
+/// `request` holds the arguments of `process_youtube_video`. The job is
+/// returned at once; progress is reported through `job-updated` events and
+/// `get_job`.
+#[command]
+pub async fn enqueue_youtube_video_job(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    queue: State<'_, JobQueueState>,
+    request: YouTubeVideoRequest,
+    max_attempts: Option<i64>,
+) -> Result<Job, String> {
+    let params = serde_json::to_value(&request).map_err(|e| e.to_string())?;
+
+    let job = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        jobs::enqueue(&conn, YOUTUBE_VIDEO_JOB, &params, max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS))?
+    };
+    let _ = app.emit(JOB_UPDATED_EVENT, &job);
+    queue.wake.notify_one();
+    Ok(job)
+}
+
+/// Get a job by id
+#[command]
+pub async fn get_job(db: State<'_, AgentDb>, job_id: i64) -> Result<Job, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    jobs::load(&conn, job_id)
+}
+
+/// List jobs newest first, optionally only those with `status`
+#[command]
+pub async fn list_jobs(
+    db: State<'_, AgentDb>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<Job>, String> {
+    let status = status.as_deref().map(JobStatus::parse).transpose()?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    jobs::list(&conn, status, limit.unwrap_or(100))
+}
+
+/// Cancel a queued or running job; a running download or ffmpeg is stopped
+#[command]
+pub async fn cancel_job(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    queue: State<'_, JobQueueState>,
+    job_id: i64,
+) -> Result<Job, String> {
+    let job = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        jobs::cancel(&conn, job_id)?
+    };
+
+    if let Some(cancel) = queue.running.lock().map_err(|e| e.to_string())?.get(&job_id) {
+        cancel.notify_one();
+    }
+    let _ = app.emit(JOB_UPDATED_EVENT, &job);
+    Ok(job)
+}
+
+/// Queue a failed or cancelled job again, resuming from its last checkpoint
+#[command]
+pub async fn retry_job(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    queue: State<'_, JobQueueState>,
+    job_id: i64,
+) -> Result<Job, String> {
+    let job = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        jobs::retry(&conn, job_id)?
+    };
+    let _ = app.emit(JOB_UPDATED_EVENT, &job);
+    queue.wake.notify_one();
+    Ok(job)
+}
+
+/// Resume jobs interrupted by the last shutdown and start the worker
+///
+/// Jobs run one at a time; downloads and ffmpeg are heavy enough that
+/// running them side by side only slows each down.
+pub fn start_job_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let resumed = {
+            let db = app.state::<AgentDb>();
+            let conn = db.0.lock().map_err(|e| e.to_string());
+            conn.and_then(|conn| jobs::requeue_interrupted(&conn))
+        };
+        match resumed {
+            Ok(0) => {}
+            Ok(count) => info!("Resuming {} interrupted jobs", count),
+            Err(e) => error!("Failed to resume jobs: {}", e),
+        }
+
+        loop {
+            let claimed = {
+                let db = app.state::<AgentDb>();
+                let conn = db.0.lock().map_err(|e| e.to_string());
+                conn.and_then(|conn| jobs::claim_next(&conn))
+            };
+
+            match claimed {
+                Ok(Some(job)) => execute_job(&app, job).await,
+                Ok(None) => {
+                    let queue = app.state::<JobQueueState>();
+                    tokio::select! {
+                        _ = queue.wake.notified() => {}
+                        _ = tokio::time::sleep(IDLE_POLL_INTERVAL) => {}
+                    }
+                }
+                Err(e) => {
+                    error!("Job worker: {}", e);
+                    tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+                }
+            }
+        }
+    });
+}
+
+// Helper functions
+
+async fn execute_job(app: &AppHandle, job: Job) {
+    let queue = app.state::<JobQueueState>();
+    let cancel = Arc::new(Notify::new());
+    if let Ok(mut running) = queue.running.lock() {
+        running.insert(job.id, cancel.clone());
+    }
+
+    // A cancel between claiming the job and registering it found nothing to notify
+    let db = app.state::<AgentDb>();
+    let status = db.0.lock().map_err(|e| e.to_string()).and_then(|conn| jobs::load(&conn, job.id));
+    if !matches!(status, Ok(Job { status: JobStatus::Running, .. })) {
+        if let Ok(mut running) = queue.running.lock() {
+            running.remove(&job.id);
+        }
+        info!("Job {} cancelled before it started", job.id);
+        emit_job(app, job.id);
+        return;
+    }
+    emit_job(app, job.id);
+    info!("Running job {} ({}), attempt {}", job.id, job.kind, job.attempts);
+
+    // Dropping the job future on cancel kills its child processes
+    let outcome = tokio::select! {
+        result = run_job(app, &job) => Some(result),
+        _ = cancel.notified() => None,
+    };
+
+    if let Ok(mut running) = queue.running.lock() {
+        running.remove(&job.id);
+    }
+
+    let saved = db.0.lock().map_err(|e| e.to_string()).and_then(|conn| match &outcome {
+        Some(Ok(result)) => jobs::complete(&conn, job.id, result),
+        Some(Err(e)) => {
+            warn!("Job {} failed: {}", job.id, e);
+            jobs::fail(&conn, job.id, e).map(|_| ())
+        }
+        None => {
+            info!("Job {} cancelled", job.id);
+            Ok(())
+        }
+    });
+    if let Err(e) = saved {
+        error!("Failed to record outcome of job {}: {}", job.id, e);
+    }
+    emit_job(app, job.id);
+}
+
+async fn run_job(app: &AppHandle, job: &Job) -> Result<JsonValue, String> {
+    match job.kind.as_str() {
+        YOUTUBE_VIDEO_JOB => {
+            let request: YouTubeVideoRequest = serde_json::from_value(job.params.clone())
+                .map_err(|e| format!("Invalid job parameters: {}", e))?;
+            // A checkpoint from an older version is ignored rather than failing the job
+            let mut progress: YouTubeVideoProgress = job.checkpoint.clone()
+                .and_then(|checkpoint| serde_json::from_value(checkpoint).ok())
+                .unwrap_or_default();
+
+            let db = app.state::<AgentDb>();
+            let providers = app.state::<TranscriptProviderState>();
+            let mut checkpoint = |stage: &str, progress: &YouTubeVideoProgress| -> Result<(), String> {
+                let value = serde_json::to_value(progress).map_err(|e| e.to_string())?;
+                {
+                    let conn = db.0.lock().map_err(|e| e.to_string())?;
+                    jobs::save_checkpoint(&conn, job.id, stage, &value)?;
+                }
+                emit_job(app, job.id);
+                Ok(())
+            };
+
+            let result = run_youtube_pipeline(app, &db, &providers, &request, &mut progress, &mut checkpoint).await?;
+            Ok(json!({
+                "video_id": result.video_info.as_ref().map(|info| info.video_id.clone()),
+                "title": result.video_info.as_ref().map(|info| info.title.clone()),
+                "manifest_path": result.manifest_path,
+                "provider": result.provider,
+            }))
+        }
+        other => Err(format!("Unknown job kind: {}", other)),
+    }
+}
+
+fn emit_job(app: &AppHandle, job_id: i64) {
+    let db = app.state::<AgentDb>();
+    let job = db.0.lock().map_err(|e| e.to_string()).and_then(|conn| jobs::load(&conn, job_id));
+    match job {
+        Ok(job) => {
+            let _ = app.emit(JOB_UPDATED_EVENT, &job);
+        }
+        Err(e) => warn!("Failed to load job {}: {}", job_id, e),
+    }
+}
@@ -0,0 +1,609 @@
+use log::{info, warn};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::manifest::{Manifest, ManifestItem};
+
+const LIBRARY_ROOT_SETTING: &str = "library_root";
+
+/// Where video folders go until a library root is configured
+const DEFAULT_LIBRARY_ROOT: &str = "~/VisionNotebook";
+
+/// The configured library root and the directory it resolves to
+#[derive(Debug, Serialize)]
+pub struct LibraryRoot {
+    /// As configured, e.g. `~/VisionNotebook`
+    pub library_root: String,
+    pub resolved_path: String,
+}
+
+/// Outcome of moving the video folders to a new library root
+#[derive(Debug, Serialize)]
+pub struct LibraryRelocation {
+    pub library_root: LibraryRoot,
+    /// Video ids whose folders were moved
+    pub moved: Vec<String>,
+    /// Videos left where they were, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// Disk space used by one video's folder
+#[derive(Debug, Serialize)]
+pub struct VideoDiskUsage {
+    pub history_id: i64,
+    pub video_id: String,
+    pub title: String,
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Get the directory new video folders are created in
+#[command]
+pub async fn get_library_root(db: State<'_, AgentDb>) -> Result<LibraryRoot, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_library_root(&conn)
+}
+
+/// Set the directory new video folders are created in
+///
+/// `~` expands to the home directory. Existing videos stay where they are;
+/// use `relocate_library` to move them.
+#[command]
+pub async fn set_library_root(db: State<'_, AgentDb>, library_root: String) -> Result<LibraryRoot, String> {
+    let resolved = resolve_library_root(&library_root)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    save_library_root(&conn, library_root.trim())?;
+    Ok(library_root_from(library_root.trim(), &resolved))
+}
+
+/// Move every video folder into `library_root` and make it the library root
+///
+/// Manifests, their frame paths and annotation frames are rewritten to the new
+/// location. A video whose folder name is already taken there is skipped, as is
+/// one that fails partway; its folder is moved back first.
+#[command]
+pub async fn relocate_library(db: State<'_, AgentDb>, library_root: String) -> Result<LibraryRelocation, String> {
+    let new_root = resolve_library_root(&library_root)?;
+    let videos: Vec<(i64, String, String)> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT id, video_id, manifest_path FROM transcript_history
+             WHERE manifest_path IS NOT NULL
+             ORDER BY id"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+    let canonical_root = new_root.canonicalize().unwrap_or_else(|_| new_root.clone());
+
+    let mut moved = Vec::new();
+    let mut skipped = Vec::new();
+    for (history_id, video_id, manifest_path) in videos {
+        let Some(old_dir) = VideoDir::locate(&manifest_path) else {
+            skipped.push(format!("{}: folder not found", video_id));
+            continue;
+        };
+        if old_dir.canonical.starts_with(&canonical_root) {
+            continue;
+        }
+
+        let moving = {
+            let new_root = new_root.clone();
+            run_blocking(move || move_video_dir(old_dir, &new_root)).await
+        };
+        let moved_dir = match moving {
+            Ok(moved_dir) => moved_dir,
+            Err(e) => {
+                warn!("Not moving {}: {}", video_id, e);
+                skipped.push(format!("{}: {}", video_id, e));
+                continue;
+            }
+        };
+
+        let rebased = {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            rebase_video_rows(&conn, history_id, &moved_dir.old_dir, &moved_dir.new_dir)
+        };
+        if let Err(e) = rebased {
+            warn!("Not moving {}: {}", video_id, e);
+            let undone = run_blocking(move || moved_dir.undo()).await;
+            skipped.push(match undone {
+                Ok(()) => format!("{}: {}", video_id, e),
+                Err(undo) => format!("{}: {}; {}", video_id, e, undo),
+            });
+            continue;
+        }
+
+        // Everything points at the new folder; a leftover original only wastes space
+        let old_path = moved_dir.old_dir.canonical.clone();
+        if let Err(e) = run_blocking(move || moved_dir.finish()).await {
+            warn!("Moved {} but left {} behind: {}", video_id, old_path.display(), e);
+        }
+        moved.push(video_id);
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    save_library_root(&conn, library_root.trim())?;
+    info!("Moved {} videos to {}", moved.len(), new_root.display());
+
+    Ok(LibraryRelocation {
+        library_root: library_root_from(library_root.trim(), &new_root),
+        moved,
+        skipped,
+    })
+}
+
+/// Disk space used by each video's folder, largest first
+#[command]
+pub async fn get_library_disk_usage(db: State<'_, AgentDb>) -> Result<Vec<VideoDiskUsage>, String> {
+    let videos: Vec<(i64, String, String, String)> = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(
+            "SELECT id, video_id, title, manifest_path FROM transcript_history
+             WHERE manifest_path IS NOT NULL"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    run_blocking(move || {
+        let mut usage: Vec<VideoDiskUsage> = videos
+            .into_iter()
+            .filter_map(|(history_id, video_id, title, manifest_path)| {
+                let dir = VideoDir::locate(&manifest_path)?.canonical;
+                let (bytes, files) = dir_size(&dir);
+                Some(VideoDiskUsage {
+                    history_id,
+                    video_id,
+                    title,
+                    path: dir.to_string_lossy().to_string(),
+                    bytes,
+                    files,
+                })
+            })
+            .collect();
+        usage.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.video_id.cmp(&b.video_id)));
+        Ok(usage)
+    }).await
+}
+
+// Helper functions
+
+/// Expand a leading `~` to the home directory
+pub fn expand_home(path: &str) -> Result<PathBuf, String> {
+    let path = path.trim();
+    let Some(rest) = path.strip_prefix('~') else {
+        return Ok(PathBuf::from(path));
+    };
+    let rest = if rest.is_empty() {
+        rest
+    } else if let Some(rest) = rest.strip_prefix(['/', '\\']) {
+        rest
+    } else {
+        return Err(format!("Unsupported home directory in {}", path));
+    };
+
+    let home = dirs::home_dir().ok_or("Could not find the home directory")?;
+    Ok(if rest.is_empty() { home } else { home.join(rest) })
+}
+
+/// The configured library root, or the default
+pub(crate) fn load_library_root(conn: &Connection) -> Result<LibraryRoot, String> {
+    let configured = conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        params![LIBRARY_ROOT_SETTING],
+        |row| row.get::<_, String>(0),
+    ).ok().filter(|value| !value.trim().is_empty());
+    let library_root = configured.unwrap_or_else(|| DEFAULT_LIBRARY_ROOT.to_string());
+    Ok(library_root_from(&library_root, &resolve_library_root(&library_root)?))
+}
+
+/// Directory a command's `project_dir` refers to: the library root when it is not given
+pub(crate) fn project_dir_path(db: &AgentDb, project_dir: Option<&str>) -> Result<PathBuf, String> {
+    match project_dir.map(str::trim).filter(|dir| !dir.is_empty()) {
+        Some(dir) => expand_home(dir),
+        None => {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            Ok(PathBuf::from(load_library_root(&conn)?.resolved_path))
+        }
+    }
+}
+
+fn library_root_from(library_root: &str, resolved: &Path) -> LibraryRoot {
+    LibraryRoot {
+        library_root: library_root.to_string(),
+        resolved_path: resolved.to_string_lossy().to_string(),
+    }
+}
+
+/// Expand a library root and require it to be absolute
+///
+/// A relative root would put videos wherever the app happens to be started.
+fn resolve_library_root(library_root: &str) -> Result<PathBuf, String> {
+    if library_root.trim().is_empty() {
+        return Err("Library root is empty".to_string());
+    }
+    let resolved = expand_home(library_root)?;
+    if !resolved.is_absolute() {
+        return Err(format!("Library root must be an absolute path: {}", library_root.trim()));
+    }
+    Ok(resolved)
+}
+
+fn save_library_root(conn: &Connection, library_root: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2",
+        params![LIBRARY_ROOT_SETTING, library_root],
+    ).map_err(|e| format!("Failed to save library root: {}", e))?;
+    Ok(())
+}
+
+/// Run file system work off the async runtime
+async fn run_blocking<T, F>(work: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work).await.map_err(|e| e.to_string())?
+}
+
+/// A video folder and the ways stored paths may spell it
+///
+/// Videos saved before the library root existed recorded paths under a
+/// relative `~/VisionNotebook`, and others may go through a symlink, so
+/// a frame path is matched against each spelling rather than only the
+/// canonical one.
+struct VideoDir {
+    /// Where the folder really is
+    canonical: PathBuf,
+    spellings: Vec<PathBuf>,
+}
+
+impl VideoDir {
+    /// The folder holding a manifest, if it still exists
+    fn locate(manifest_path: &str) -> Option<Self> {
+        let stored = Path::new(manifest_path).parent()?;
+        let canonical = stored.canonicalize().ok()?;
+        let mut spellings = vec![stored.to_path_buf()];
+        spellings.extend(std::path::absolute(stored).ok());
+        spellings.push(canonical.clone());
+        spellings.dedup();
+        Some(Self { canonical, spellings })
+    }
+
+    /// `path` moved from under this folder to under `new_dir`; None if it was elsewhere
+    fn rebase(&self, path: &str, new_dir: &Path) -> Option<String> {
+        let path = Path::new(path);
+        let absolute = std::path::absolute(path).ok();
+        let rest = self.spellings.iter().find_map(|dir| {
+            path.strip_prefix(dir).ok()
+                .or_else(|| absolute.as_deref()?.strip_prefix(dir).ok())
+        })?;
+        Some(new_dir.join(rest).to_string_lossy().to_string())
+    }
+}
+
+/// A video folder placed under the new root, not yet committed to
+struct MovedDir {
+    old_dir: VideoDir,
+    new_dir: PathBuf,
+    /// Renamed rather than copied, so the original is gone
+    renamed: bool,
+    /// The manifest as it was, to restore on undo
+    original_manifest: Option<Vec<u8>>,
+}
+
+impl MovedDir {
+    /// Point the manifest's frames and thumbnail at the new folder
+    fn rewrite_manifest(&self) -> Result<(), String> {
+        if self.original_manifest.is_none() {
+            return Ok(());
+        }
+        let manifest_path = self.new_dir.join("manifest.json");
+        let mut manifest = Manifest::load(&manifest_path)?;
+        // Local videos use their first frame as the thumbnail
+        if let Some(thumbnail) = &mut manifest.video.thumbnail_url {
+            if let Some(rebased) = self.old_dir.rebase(thumbnail, &self.new_dir) {
+                *thumbnail = rebased;
+            }
+        }
+        for item in &mut manifest.items {
+            if let ManifestItem::Image { path, .. } = item {
+                if let Some(rebased) = self.old_dir.rebase(path, &self.new_dir) {
+                    *path = rebased;
+                }
+            }
+        }
+        manifest.save(&manifest_path)
+    }
+
+    /// Put the folder back as it was
+    fn undo(self) -> Result<(), String> {
+        if !self.renamed {
+            return std::fs::remove_dir_all(&self.new_dir)
+                .map_err(|e| format!("failed to remove the copy at {}: {}", self.new_dir.display(), e));
+        }
+        if let Some(original) = &self.original_manifest {
+            std::fs::write(self.new_dir.join("manifest.json"), original)
+                .map_err(|e| format!("failed to restore the manifest in {}: {}", self.new_dir.display(), e))?;
+        }
+        std::fs::rename(&self.new_dir, &self.old_dir.canonical)
+            .map_err(|e| format!("failed to move {} back: {}", self.new_dir.display(), e))
+    }
+
+    /// Remove the original once nothing refers to it
+    fn finish(self) -> Result<(), String> {
+        if self.renamed {
+            return Ok(());
+        }
+        std::fs::remove_dir_all(&self.old_dir.canonical).map_err(|e| e.to_string())
+    }
+}
+
+/// Move a video folder into `new_root` and rewrite its manifest's frame paths
+///
+/// A folder that has to be copied keeps its original until `finish`, and
+/// one that fails here is put back before the error is returned.
+fn move_video_dir(old_dir: VideoDir, new_root: &Path) -> Result<MovedDir, String> {
+    let name = old_dir.canonical.file_name().ok_or("Video folder has no name")?;
+    let new_dir = new_root.join(name);
+    if new_dir.exists() {
+        return Err(format!("{} already exists", new_dir.display()));
+    }
+    std::fs::create_dir_all(new_root)
+        .map_err(|e| format!("Failed to create {}: {}", new_root.display(), e))?;
+    let original_manifest = std::fs::read(old_dir.canonical.join("manifest.json")).ok();
+
+    // A rename fails across file systems; copy instead
+    let renamed = std::fs::rename(&old_dir.canonical, &new_dir).is_ok();
+    if !renamed {
+        if let Err(e) = copy_dir(&old_dir.canonical, &new_dir) {
+            let _ = std::fs::remove_dir_all(&new_dir);
+            return Err(e);
+        }
+    }
+
+    let moved = MovedDir { old_dir, new_dir, renamed, original_manifest };
+    match moved.rewrite_manifest() {
+        Ok(()) => Ok(moved),
+        Err(e) => Err(match moved.undo() {
+            Ok(()) => e,
+            Err(undo) => format!("{}; {}", e, undo),
+        }),
+    }
+}
+
+/// Point a history entry, its thumbnail and its annotations at a moved folder, all or nothing
+fn rebase_video_rows(conn: &Connection, history_id: i64, old_dir: &VideoDir, new_dir: &Path) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let thumbnail: Option<String> = tx.query_row(
+        "SELECT thumbnail_url FROM transcript_history WHERE id = ?1",
+        params![history_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to get transcript details: {}", e))?;
+    let thumbnail = thumbnail.map(|url| old_dir.rebase(&url, new_dir).unwrap_or(url));
+    tx.execute(
+        "UPDATE transcript_history SET manifest_path = ?1, thumbnail_url = ?2 WHERE id = ?3",
+        params![new_dir.join("manifest.json").to_string_lossy().to_string(), thumbnail, history_id],
+    ).map_err(|e| format!("Failed to update manifest path: {}", e))?;
+
+    let frames = {
+        let mut stmt = tx.prepare(
+            "SELECT id, frame_path FROM transcript_annotations
+             WHERE history_id = ?1 AND frame_path IS NOT NULL"
+        ).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map(params![history_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        rows
+    };
+
+    for (annotation_id, frame_path) in frames {
+        if let Some(rebased) = old_dir.rebase(&frame_path, new_dir) {
+            tx.execute(
+                "UPDATE transcript_annotations SET frame_path = ?1 WHERE id = ?2",
+                params![rebased, annotation_id],
+            ).map_err(|e| format!("Failed to update annotation frame: {}", e))?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let entries = std::fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let Some(name) = path.file_name() else { continue };
+        if path.is_dir() {
+            copy_dir(&path, &to.join(name))?;
+        } else {
+            std::fs::copy(&path, to.join(name))
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Total size and number of files under a directory
+fn dir_size(dir: &Path) -> (u64, u64) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (0, 0);
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .fold((0, 0), |(bytes, files), entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => {
+                let (sub_bytes, sub_files) = dir_size(&entry.path());
+                (bytes + sub_bytes, files + sub_files)
+            }
+            Ok(meta) => (bytes + meta.len(), files + 1),
+            Err(_) => (bytes, files),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+    use crate::commands::vision_notebook::{TranscriptSegment, VideoInfo};
+    use crate::media::SampledFrame;
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~").unwrap(), home);
+        assert_eq!(expand_home(" ~/VisionNotebook ").unwrap(), home.join("VisionNotebook"));
+        assert_eq!(expand_home("/data/videos").unwrap(), PathBuf::from("/data/videos"));
+        assert!(expand_home("~alice/videos").is_err());
+        assert!(resolve_library_root("videos").is_err());
+    }
+
+    #[test]
+    fn test_library_root_setting() {
+        let conn = open_test_db();
+        let default = load_library_root(&conn).unwrap();
+        assert_eq!(default.library_root, "~/VisionNotebook");
+        assert!(Path::new(&default.resolved_path).is_absolute());
+
+        save_library_root(&conn, "/srv/notebook").unwrap();
+        assert_eq!(load_library_root(&conn).unwrap().resolved_path, "/srv/notebook");
+    }
+
+    /// A video folder with one frame and a manifest recording it as `frame` and the thumbnail
+    fn write_video(dir: &Path, frame: &Path) {
+        std::fs::create_dir_all(dir.join("frames")).unwrap();
+        std::fs::write(dir.join("frames/frame_000001.jpg"), b"jpeg").unwrap();
+
+        let video = VideoInfo {
+            video_id: "abc".to_string(),
+            title: "Talk".to_string(),
+            channel: String::new(),
+            duration: None,
+            thumbnail_url: Some(frame.to_string_lossy().to_string()),
+        };
+        let transcript = vec![TranscriptSegment { text: "hi".to_string(), start: 0.0, duration: 1.0 }];
+        let frames = vec![SampledFrame { path: frame.to_path_buf(), t: 0.0 }];
+        Manifest::build(&video, &transcript, &frames, None, None)
+            .save(&dir.join("manifest.json"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_move_video_dir_rewrites_paths() {
+        let old_root = tempfile::tempdir().unwrap();
+        let new_root = tempfile::tempdir().unwrap();
+        let old_dir = old_root.path().join("abc");
+        let frame = old_dir.join("frames/frame_000001.jpg").to_string_lossy().to_string();
+        write_video(&old_dir, Path::new(&frame));
+
+        // A local video's thumbnail is its first frame
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Talk", "Channel");
+        conn.execute(
+            "UPDATE transcript_history SET manifest_path = ?1, thumbnail_url = ?2 WHERE id = ?3",
+            params![old_dir.join("manifest.json").to_string_lossy().to_string(), &frame, history_id],
+        ).unwrap();
+
+        let located = VideoDir::locate(&old_dir.join("manifest.json").to_string_lossy()).unwrap();
+        let moved = move_video_dir(located, new_root.path()).unwrap();
+        rebase_video_rows(&conn, history_id, &moved.old_dir, &moved.new_dir).unwrap();
+        let new_dir = moved.new_dir.clone();
+        moved.finish().unwrap();
+        assert!(!old_dir.exists());
+        assert_eq!(dir_size(&new_dir).1, 2);
+
+        let manifest = Manifest::load(&new_dir.join("manifest.json")).unwrap();
+        let moved_frame = new_dir.join("frames/frame_000001.jpg").to_string_lossy().to_string();
+        assert_eq!(manifest.frame_at(0.0), Some(moved_frame.as_str()));
+        assert_eq!(manifest.video.thumbnail_url.as_deref(), Some(moved_frame.as_str()));
+        let thumbnail: String = conn.query_row(
+            "SELECT thumbnail_url FROM transcript_history WHERE id = ?1",
+            params![history_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(thumbnail, moved_frame);
+
+        // The destination is never overwritten
+        write_video(&old_dir, &old_dir.join("frames/frame_000001.jpg"));
+        let located = VideoDir::locate(&old_dir.join("manifest.json").to_string_lossy()).unwrap();
+        assert!(move_video_dir(located, new_root.path()).err().unwrap().contains("already exists"));
+    }
+
+    #[test]
+    fn test_relocate_legacy_relative_paths() {
+        // Stored as a relative path with `..`, as the literal `~/VisionNotebook` once was
+        let old_root = tempfile::tempdir().unwrap();
+        let new_root = tempfile::tempdir().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let mut relative = PathBuf::new();
+        for _ in cwd.components().filter(|c| matches!(c, std::path::Component::Normal(_))) {
+            relative.push("..");
+        }
+        for component in old_root.path().components() {
+            if let std::path::Component::Normal(name) = component {
+                relative.push(name);
+            }
+        }
+        let stored_dir = relative.join("abc");
+        let stored_frame = stored_dir.join("frames/frame_000001.jpg");
+        write_video(&stored_dir, &stored_frame);
+        let manifest_path = stored_dir.join("manifest.json");
+
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "abc", "Talk", "Channel");
+        conn.execute(
+            "UPDATE transcript_history SET manifest_path = ?1 WHERE id = ?2",
+            params![manifest_path.to_string_lossy().to_string(), history_id],
+        ).unwrap();
+        conn.execute(
+            "INSERT INTO transcript_annotations (history_id, t, text, frame_path) VALUES (?1, 0, 'note', ?2)",
+            params![history_id, stored_frame.to_string_lossy().to_string()],
+        ).unwrap();
+
+        let located = VideoDir::locate(&manifest_path.to_string_lossy()).unwrap();
+        let moved = move_video_dir(located, new_root.path()).unwrap();
+        rebase_video_rows(&conn, history_id, &moved.old_dir, &moved.new_dir).unwrap();
+        let new_dir = moved.new_dir.clone();
+        moved.finish().unwrap();
+
+        let moved_frame = new_dir.join("frames/frame_000001.jpg").to_string_lossy().to_string();
+        let manifest = Manifest::load(&new_dir.join("manifest.json")).unwrap();
+        assert_eq!(manifest.frame_at(0.0), Some(moved_frame.as_str()));
+        let annotation_frame: String = conn.query_row(
+            "SELECT frame_path FROM transcript_annotations WHERE history_id = ?1",
+            params![history_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(annotation_frame, moved_frame);
+        assert!(Path::new(&annotation_frame).exists());
+    }
+
+    #[test]
+    fn test_undo_restores_folder_and_manifest() {
+        let old_root = tempfile::tempdir().unwrap();
+        let new_root = tempfile::tempdir().unwrap();
+        let old_dir = old_root.path().join("abc");
+        let frame = old_dir.join("frames/frame_000001.jpg");
+        write_video(&old_dir, &frame);
+
+        let located = VideoDir::locate(&old_dir.join("manifest.json").to_string_lossy()).unwrap();
+        move_video_dir(located, new_root.path()).unwrap().undo().unwrap();
+        assert!(!new_root.path().join("abc").exists());
+        let manifest = Manifest::load(&old_dir.join("manifest.json")).unwrap();
+        assert_eq!(manifest.frame_at(0.0), Some(frame.to_string_lossy().as_ref()));
+        assert_eq!(VideoDir::locate(&old_dir.join("manifest.json").to_string_lossy()).unwrap()
+            .rebase("/elsewhere/frame.jpg", new_root.path()), None);
+    }
+}
@@ -198,5 +198,427 @@ pub fn run_migrations(conn: &Connection) -> SqliteResult<()> {
         current_version = 3;
     }
 
+    // Migration 4: Record which transcript provider produced each transcript
+    if current_version < 4 {
+        let _ = conn.execute("ALTER TABLE transcript_history ADD COLUMN provider TEXT", []);
+
+        // Everything fetched before providers existed came from YouTube captions
+        conn.execute(
+            "UPDATE transcript_history SET provider = 'youtube_captions' WHERE provider IS NULL",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [4],
+        )?;
+        current_version = 4;
+    }
+
+    // Migration 5: Distinguish YouTube videos from imported local material
+    if current_version < 5 {
+        let _ = conn.execute(
+            "ALTER TABLE transcript_history ADD COLUMN source_type TEXT NOT NULL DEFAULT 'youtube'",
+            [],
+        );
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [5],
+        )?;
+        current_version = 5;
+    }
+
+    // Migration 6: Several caption languages per video
+    if current_version < 6 {
+        // transcript_history keeps the primary track; this records its language
+        let _ = conn.execute("ALTER TABLE transcript_history ADD COLUMN language_code TEXT", []);
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcript_tracks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                language_code TEXT NOT NULL,
+                is_auto_generated BOOLEAN NOT NULL DEFAULT 0,
+                provider TEXT,
+                transcript_length INTEGER NOT NULL,
+                transcript_text TEXT NOT NULL,
+                fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(history_id, language_code),
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [6],
+        )?;
+        current_version = 6;
+    }
+
+    // Migration 7: Saved quizzes and attempts
+    if current_version < 7 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS quizzes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                difficulty TEXT,
+                questions TEXT NOT NULL,
+                question_count INTEGER NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS quiz_attempts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                quiz_id INTEGER NOT NULL,
+                taker TEXT,
+                correct_count INTEGER NOT NULL,
+                question_count INTEGER NOT NULL,
+                score REAL NOT NULL,
+                completed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (quiz_id) REFERENCES quizzes(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS quiz_attempt_answers (
+                attempt_id INTEGER NOT NULL,
+                question_index INTEGER NOT NULL,
+                selected_answer INTEGER,
+                is_correct BOOLEAN NOT NULL,
+                PRIMARY KEY (attempt_id, question_index),
+                FOREIGN KEY (attempt_id) REFERENCES quiz_attempts(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_quizzes_history ON quizzes(history_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_quiz_attempts_quiz ON quiz_attempts(quiz_id)", [])?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [7],
+        )?;
+        current_version = 7;
+    }
+
+    // Migration 8: Spaced-repetition review cards
+    if current_version < 8 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS review_cards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                quiz_id INTEGER,
+                question_text TEXT NOT NULL,
+                question TEXT NOT NULL,
+                timestamp REAL,
+                ease REAL NOT NULL DEFAULT 2.5,
+                interval_days INTEGER NOT NULL DEFAULT 0,
+                repetitions INTEGER NOT NULL DEFAULT 0,
+                lapses INTEGER NOT NULL DEFAULT 0,
+                due_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                last_reviewed_at TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(history_id, question_text),
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_review_cards_due ON review_cards(due_at)", [])?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [8],
+        )?;
+        current_version = 8;
+    }
+
+    // Migration 9: Chapters stored with the transcript
+    if current_version < 9 {
+        let _ = conn.execute("ALTER TABLE transcript_history ADD COLUMN chapters TEXT", []);
+        // 'description' for creator chapters, 'segmentation' or 'llm' for detected ones
+        let _ = conn.execute("ALTER TABLE transcript_history ADD COLUMN chapter_source TEXT", []);
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [9],
+        )?;
+        current_version = 9;
+    }
+
+    // Migration 10: Full-text index over individual transcript segments
+    if current_version < 10 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcript_segments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                start REAL NOT NULL,
+                duration REAL NOT NULL,
+                text TEXT NOT NULL,
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transcript_segments_history ON transcript_segments(history_id, position)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS transcript_segments_fts USING fts5(
+                text,
+                content = 'transcript_segments',
+                content_rowid = 'id',
+                tokenize = 'unicode61 remove_diacritics 2'
+            )",
+            [],
+        )?;
+
+        // Keep the external-content index in step with the segment rows
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS transcript_segments_ai
+             AFTER INSERT ON transcript_segments
+             BEGIN
+                 INSERT INTO transcript_segments_fts (rowid, text) VALUES (NEW.id, NEW.text);
+             END",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS transcript_segments_ad
+             AFTER DELETE ON transcript_segments
+             BEGIN
+                 INSERT INTO transcript_segments_fts (transcript_segments_fts, rowid, text)
+                 VALUES ('delete', OLD.id, OLD.text);
+             END",
+            [],
+        )?;
+
+        // Index transcripts saved before this migration
+        conn.execute(
+            "INSERT INTO transcript_segments (history_id, position, start, duration, text)
+             SELECT th.id, CAST(j.key AS INTEGER),
+                    COALESCE(json_extract(j.value, '$.start'), 0),
+                    COALESCE(json_extract(j.value, '$.duration'), 0),
+                    json_extract(j.value, '$.text')
+             FROM transcript_history th, json_each(th.transcript_text) j
+             WHERE json_valid(th.transcript_text)
+             AND json_type(th.transcript_text) = 'array'
+             AND json_extract(j.value, '$.text') IS NOT NULL",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [10],
+        )?;
+        current_version = 10;
+    }
+
+    // Migration 11: Add background job queue
+    if current_version < 11 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                params TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'queued',
+                stage TEXT,
+                checkpoint TEXT,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                max_attempts INTEGER NOT NULL DEFAULT 3,
+                run_after TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                last_error TEXT,
+                result TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_jobs_status ON jobs(status, run_after)",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [11],
+        )?;
+        current_version = 11;
+    }
+
+    // Migration 12: Mark transcripts produced by local speech-to-text
+    if current_version < 12 {
+        let _ = conn.execute(
+            "ALTER TABLE transcript_history ADD COLUMN is_machine_transcribed BOOLEAN NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE transcript_tracks ADD COLUMN is_machine_transcribed BOOLEAN NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [12],
+        )?;
+        current_version = 12;
+    }
+
+    // Migration 13: Keep a cleaned-up copy of each transcript beside the raw captions
+    if current_version < 13 {
+        let _ = conn.execute(
+            "ALTER TABLE transcript_history ADD COLUMN cleaned_transcript TEXT",
+            [],
+        );
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [13],
+        )?;
+        current_version = 13;
+    }
+
+    // Migration 14: Notes attached to moments in a video
+    if current_version < 14 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcript_annotations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                t REAL NOT NULL,
+                text TEXT NOT NULL,
+                tags TEXT,
+                frame_path TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_transcript_annotations_history
+             ON transcript_annotations(history_id, t)",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [14],
+        )?;
+        current_version = 14;
+    }
+
+    // Migration 15: Clip bookmarks for cutting highlights out of videos
+    if current_version < 15 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS clip_bookmarks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                history_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                start REAL NOT NULL,
+                end REAL NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (history_id) REFERENCES transcript_history(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_clip_bookmarks_history
+             ON clip_bookmarks(history_id, start)",
+            [],
+        )?;
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [15],
+        )?;
+        current_version = 15;
+    }
+
+    // Migration 16: Keep the latest whole-video summary for exports
+    if current_version < 16 {
+        let _ = conn.execute(
+            "ALTER TABLE transcript_history ADD COLUMN summary TEXT",
+            [],
+        );
+
+        // Update schema version
+        conn.execute(
+            "INSERT INTO schema_version (version) VALUES (?1)",
+            [16],
+        )?;
+        current_version = 16;
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// In-memory database with the transcript tables and every migration applied
+#[cfg(test)]
+pub(crate) fn open_test_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("open in-memory database");
+    // Created by the agents database setup before migrations run
+    conn.execute(
+        "CREATE TABLE transcript_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            video_id TEXT NOT NULL,
+            video_url TEXT NOT NULL,
+            title TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            duration TEXT,
+            thumbnail_url TEXT,
+            transcript_length INTEGER NOT NULL,
+            transcript_text TEXT NOT NULL,
+            manifest_path TEXT,
+            fetched_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(video_id)
+        )",
+        [],
+    )
+    .expect("create transcript_history");
+    conn.execute(
+        "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        [],
+    )
+    .expect("create app_settings");
+    run_migrations(&conn).expect("run migrations");
+    conn
+}
+
+/// Add a video with an empty transcript to a test database, returning its history id
+#[cfg(test)]
+pub(crate) fn insert_test_video(conn: &Connection, video_id: &str, title: &str, channel: &str) -> i64 {
+    conn.execute(
+        "INSERT INTO transcript_history
+         (video_id, video_url, title, channel, transcript_length, transcript_text)
+         VALUES (?1, ?2, ?3, ?4, 0, '[]')",
+        rusqlite::params![video_id, format!("https://www.youtube.com/watch?v={}", video_id), title, channel],
+    )
+    .expect("insert transcript_history");
+    conn.last_insert_rowid()
+}
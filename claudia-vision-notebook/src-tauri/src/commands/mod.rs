@@ -5,5 +5,19 @@ pub mod usage;
 pub mod storage;
 pub mod slash_commands;
 pub mod vision_notebook;
+pub mod chapters;
+pub mod subtitles;
+pub mod quiz;
+pub mod review;
+pub mod search;
+pub mod ingest;
+pub mod jobs;
+pub mod whisper;
+pub mod cleanup;
+pub mod annotations;
+pub mod clips;
+pub mod vault;
+pub mod library;
+pub mod transcript_tracks;
 pub mod history;
 pub mod migrations;
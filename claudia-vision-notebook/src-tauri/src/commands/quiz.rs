@@ -0,0 +1,503 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::history::{create_notebook_entry, delete_notebook_entries};
+use crate::commands::vision_notebook::QuizQuestion;
+
+/// A quiz saved against a transcript history entry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedQuiz {
+    pub id: i64,
+    pub history_id: i64,
+    pub video_id: String,
+    pub video_title: String,
+    pub title: String,
+    pub difficulty: Option<String>,
+    pub questions: Vec<QuizQuestion>,
+    pub created_at: String,
+}
+
+/// The answer given to one question in an attempt
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizAnswer {
+    pub question_index: usize,
+    /// None when the question was skipped
+    pub selected_answer: Option<usize>,
+    pub is_correct: bool,
+}
+
+/// One completed run through a quiz
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizAttempt {
+    pub id: i64,
+    pub quiz_id: i64,
+    pub taker: Option<String>,
+    pub answers: Vec<QuizAnswer>,
+    pub correct_count: i64,
+    pub question_count: i64,
+    /// Percentage of questions answered correctly
+    pub score: f64,
+    pub completed_at: String,
+}
+
+/// A single attempt's score, for charting progress
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScorePoint {
+    pub quiz_id: i64,
+    pub score: f64,
+    pub completed_at: String,
+}
+
+/// Scores grouped by video or by tag
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoreSummary {
+    /// Video id or tag
+    pub key: String,
+    /// Video title or tag
+    pub label: String,
+    pub attempts: i64,
+    pub average_score: f64,
+    pub best_score: f64,
+    pub latest_score: f64,
+    /// Every attempt in completion order
+    pub history: Vec<ScorePoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuizScoreReport {
+    pub by_video: Vec<ScoreSummary>,
+    pub by_tag: Vec<ScoreSummary>,
+}
+
+/// Save generated questions as a quiz and add it to the notebook
+#[command]
+pub async fn save_quiz(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    questions: Vec<QuizQuestion>,
+    title: Option<String>,
+    difficulty: Option<String>,
+) -> Result<SavedQuiz, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let quiz_id = insert_quiz(&conn, history_id, &questions, title.as_deref(), difficulty.as_deref())?;
+    load_quiz(&conn, quiz_id)
+}
+
+/// List saved quizzes, optionally for one transcript
+#[command]
+pub async fn get_quizzes(
+    db: State<'_, AgentDb>,
+    history_id: Option<i64>,
+) -> Result<Vec<SavedQuiz>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_quizzes(&conn, history_id)
+}
+
+/// Delete a quiz, its attempts and its notebook entry
+#[command]
+pub async fn delete_quiz(
+    db: State<'_, AgentDb>,
+    quiz_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    delete_quiz_rows(&tx, quiz_id)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Score and record an attempt; `answers` holds the selected option per question
+#[command]
+pub async fn submit_quiz_attempt(
+    db: State<'_, AgentDb>,
+    quiz_id: i64,
+    answers: Vec<Option<usize>>,
+    taker: Option<String>,
+) -> Result<QuizAttempt, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let attempt_id = record_attempt(&conn, quiz_id, &answers, taker.as_deref())?;
+    load_attempt(&conn, attempt_id)
+}
+
+/// List attempts at a quiz, newest first
+#[command]
+pub async fn get_quiz_attempts(
+    db: State<'_, AgentDb>,
+    quiz_id: i64,
+) -> Result<Vec<QuizAttempt>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id FROM quiz_attempts WHERE quiz_id = ?1 ORDER BY completed_at DESC, id DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let ids = stmt.query_map(params![quiz_id], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    ids.into_iter().map(|id| load_attempt(&conn, id)).collect()
+}
+
+/// Scores over time per video and per quiz tag, optionally for one taker
+#[command]
+pub async fn get_quiz_scores(
+    db: State<'_, AgentDb>,
+    taker: Option<String>,
+) -> Result<QuizScoreReport, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_score_report(&conn, taker.as_deref())
+}
+
+// Helper functions
+
+fn insert_quiz(
+    conn: &Connection,
+    history_id: i64,
+    questions: &[QuizQuestion],
+    title: Option<&str>,
+    difficulty: Option<&str>,
+) -> Result<i64, String> {
+    if questions.is_empty() {
+        return Err("A quiz needs at least one question".to_string());
+    }
+    check_answers(questions)?;
+
+    let (video_title, channel): (String, String) = conn.query_row(
+        "SELECT title, channel FROM transcript_history WHERE id = ?1",
+        params![history_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Failed to get transcript details: {}", e))?;
+
+    // Quizzes start with the tags of the transcript they were generated from
+    let tags: Option<String> = conn.query_row(
+        "SELECT tags FROM notebook_entries WHERE entry_type = 'transcript' AND entry_id = ?1",
+        params![history_id],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?.flatten();
+    let tags = tags.unwrap_or(channel);
+
+    let title = title.map(|t| t.to_string()).unwrap_or_else(|| format!("Quiz: {}", video_title));
+    let questions_json = serde_json::to_string(questions)
+        .map_err(|e| format!("Failed to serialize quiz: {}", e))?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO quizzes (history_id, title, difficulty, questions, question_count)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![history_id, &title, difficulty, &questions_json, questions.len() as i64],
+    ).map_err(|e| format!("Failed to save quiz: {}", e))?;
+    let quiz_id = tx.last_insert_rowid();
+
+    let preview: String = questions.iter()
+        .map(|q| q.question.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(200)
+        .collect();
+
+    create_notebook_entry(&tx, "quiz", Some(quiz_id), &title, Some(&preview), Some(&tags))
+        .map_err(|e| format!("Failed to create notebook entry: {}", e))?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(quiz_id)
+}
+
+/// Reject questions whose correct answer is not one of their options
+pub(crate) fn check_answers(questions: &[QuizQuestion]) -> Result<(), String> {
+    for (i, question) in questions.iter().enumerate() {
+        if question.correct_answer >= question.options.len() {
+            return Err(format!(
+                "Question {} has correct_answer {} but only {} options",
+                i + 1,
+                question.correct_answer,
+                question.options.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn load_quiz(conn: &Connection, quiz_id: i64) -> Result<SavedQuiz, String> {
+    conn.query_row(
+        "SELECT q.id, q.history_id, th.video_id, th.title, q.title, q.difficulty, q.questions, q.created_at
+         FROM quizzes q
+         JOIN transcript_history th ON th.id = q.history_id
+         WHERE q.id = ?1",
+        params![quiz_id],
+        |row| {
+            let questions_json: String = row.get(6)?;
+            Ok(SavedQuiz {
+                id: row.get(0)?,
+                history_id: row.get(1)?,
+                video_id: row.get(2)?,
+                video_title: row.get(3)?,
+                title: row.get(4)?,
+                difficulty: row.get(5)?,
+                questions: serde_json::from_str(&questions_json).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e))
+                })?,
+                created_at: row.get(7)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to get quiz: {}", e))
+}
+
+/// Saved quizzes newest first, optionally for one transcript
+pub(crate) fn load_quizzes(conn: &Connection, history_id: Option<i64>) -> Result<Vec<SavedQuiz>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM quizzes
+         WHERE ?1 IS NULL OR history_id = ?1
+         ORDER BY created_at DESC, id DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let ids = stmt.query_map(params![history_id], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    ids.into_iter().map(|id| load_quiz(conn, id)).collect()
+}
+
+/// Delete a quiz with its attempts and notebook entry inside the caller's transaction
+pub(crate) fn delete_quiz_rows(tx: &Transaction, quiz_id: i64) -> Result<(), String> {
+    tx.execute(
+        "DELETE FROM quiz_attempt_answers
+         WHERE attempt_id IN (SELECT id FROM quiz_attempts WHERE quiz_id = ?1)",
+        params![quiz_id],
+    ).map_err(|e| format!("Failed to delete quiz attempts: {}", e))?;
+    tx.execute("DELETE FROM quiz_attempts WHERE quiz_id = ?1", params![quiz_id])
+        .map_err(|e| format!("Failed to delete quiz attempts: {}", e))?;
+    tx.execute("DELETE FROM quizzes WHERE id = ?1", params![quiz_id])
+        .map_err(|e| format!("Failed to delete quiz: {}", e))?;
+
+    delete_notebook_entries(tx, "quiz", quiz_id)
+        .map_err(|e| format!("Failed to delete notebook entry: {}", e))
+}
+
+fn record_attempt(
+    conn: &Connection,
+    quiz_id: i64,
+    answers: &[Option<usize>],
+    taker: Option<&str>,
+) -> Result<i64, String> {
+    let questions = load_quiz(conn, quiz_id)?.questions;
+    if answers.len() != questions.len() {
+        return Err(format!(
+            "Expected {} answers but got {}",
+            questions.len(),
+            answers.len()
+        ));
+    }
+
+    let correct: Vec<bool> = questions.iter()
+        .zip(answers)
+        .map(|(question, answer)| *answer == Some(question.correct_answer))
+        .collect();
+    let correct_count = correct.iter().filter(|c| **c).count();
+    let score = correct_count as f64 * 100.0 / questions.len() as f64;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO quiz_attempts (quiz_id, taker, correct_count, question_count, score)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![quiz_id, taker, correct_count as i64, questions.len() as i64, score],
+    ).map_err(|e| format!("Failed to save quiz attempt: {}", e))?;
+    let attempt_id = tx.last_insert_rowid();
+
+    for (index, (answer, is_correct)) in answers.iter().zip(&correct).enumerate() {
+        tx.execute(
+            "INSERT INTO quiz_attempt_answers (attempt_id, question_index, selected_answer, is_correct)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![attempt_id, index as i64, answer.map(|a| a as i64), is_correct],
+        ).map_err(|e| format!("Failed to save quiz answer: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(attempt_id)
+}
+
+fn load_attempt(conn: &Connection, attempt_id: i64) -> Result<QuizAttempt, String> {
+    let mut stmt = conn.prepare(
+        "SELECT question_index, selected_answer, is_correct
+         FROM quiz_attempt_answers
+         WHERE attempt_id = ?1
+         ORDER BY question_index"
+    ).map_err(|e| e.to_string())?;
+
+    let answers = stmt.query_map(params![attempt_id], |row| {
+        Ok(QuizAnswer {
+            question_index: row.get::<_, i64>(0)? as usize,
+            selected_answer: row.get::<_, Option<i64>>(1)?.map(|a| a as usize),
+            is_correct: row.get(2)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, quiz_id, taker, correct_count, question_count, score, completed_at
+         FROM quiz_attempts WHERE id = ?1",
+        params![attempt_id],
+        |row| {
+            Ok(QuizAttempt {
+                id: row.get(0)?,
+                quiz_id: row.get(1)?,
+                taker: row.get(2)?,
+                answers,
+                correct_count: row.get(3)?,
+                question_count: row.get(4)?,
+                score: row.get(5)?,
+                completed_at: row.get(6)?,
+            })
+        },
+    ).map_err(|e| format!("Failed to get quiz attempt: {}", e))
+}
+
+fn load_score_report(conn: &Connection, taker: Option<&str>) -> Result<QuizScoreReport, String> {
+    let mut stmt = conn.prepare(
+        "SELECT a.quiz_id, a.score, a.completed_at, th.video_id, th.title, ne.tags
+         FROM quiz_attempts a
+         JOIN quizzes q ON q.id = a.quiz_id
+         JOIN transcript_history th ON th.id = q.history_id
+         LEFT JOIN notebook_entries ne ON ne.entry_type = 'quiz' AND ne.entry_id = q.id
+         WHERE ?1 IS NULL OR a.taker = ?1
+         ORDER BY a.completed_at, a.id"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![taker], |row| {
+        Ok((
+            ScorePoint {
+                quiz_id: row.get(0)?,
+                score: row.get(1)?,
+                completed_at: row.get(2)?,
+            },
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+        ))
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut by_video: BTreeMap<String, (String, Vec<ScorePoint>)> = BTreeMap::new();
+    let mut by_tag: BTreeMap<String, Vec<ScorePoint>> = BTreeMap::new();
+
+    for (point, video_id, video_title, tags) in rows {
+        for tag in tags.as_deref().unwrap_or("").split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            by_tag.entry(tag.to_string()).or_default().push(ScorePoint {
+                quiz_id: point.quiz_id,
+                score: point.score,
+                completed_at: point.completed_at.clone(),
+            });
+        }
+        by_video.entry(video_id).or_insert_with(|| (video_title, Vec::new())).1.push(point);
+    }
+
+    Ok(QuizScoreReport {
+        by_video: by_video.into_iter()
+            .map(|(key, (label, history))| summarize_scores(key, label, history))
+            .collect(),
+        by_tag: by_tag.into_iter()
+            .map(|(tag, history)| summarize_scores(tag.clone(), tag, history))
+            .collect(),
+    })
+}
+
+fn summarize_scores(key: String, label: String, history: Vec<ScorePoint>) -> ScoreSummary {
+    let scores: Vec<f64> = history.iter().map(|p| p.score).collect();
+    ScoreSummary {
+        key,
+        label,
+        attempts: scores.len() as i64,
+        average_score: scores.iter().sum::<f64>() / scores.len().max(1) as f64,
+        best_score: scores.iter().copied().fold(0.0, f64::max),
+        latest_score: scores.last().copied().unwrap_or(0.0),
+        history,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    fn question(correct_answer: usize) -> QuizQuestion {
+        QuizQuestion {
+            question: format!("Which option is {}?", correct_answer),
+            options: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            correct_answer,
+            explanation: String::new(),
+            timestamp: Some(10.0),
+        }
+    }
+
+    #[test]
+    fn test_attempts_are_scored_per_question() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "vid1", "Video vid1", "Onboarding");
+        let quiz_id = insert_quiz(&conn, history_id, &[question(0), question(2), question(3)], None, Some("easy")).unwrap();
+
+        let quiz = load_quiz(&conn, quiz_id).unwrap();
+        assert_eq!(quiz.title, "Quiz: Video vid1");
+        assert_eq!(quiz.questions.len(), 3);
+
+        let attempt_id = record_attempt(&conn, quiz_id, &[Some(0), Some(1), None], Some("sam")).unwrap();
+        let attempt = load_attempt(&conn, attempt_id).unwrap();
+        assert_eq!(attempt.correct_count, 1);
+        assert!((attempt.score - 33.333).abs() < 0.01);
+        assert_eq!(attempt.answers.iter().map(|a| a.is_correct).collect::<Vec<_>>(), vec![true, false, false]);
+        assert_eq!(attempt.answers[2].selected_answer, None);
+
+        assert!(record_attempt(&conn, quiz_id, &[Some(0)], None).is_err());
+        assert!(insert_quiz(&conn, history_id, &[question(4)], None, None).unwrap_err().contains("correct_answer 4"));
+
+        // A corrupt quiz is an error rather than an empty quiz
+        conn.execute("UPDATE quizzes SET questions = 'not json' WHERE id = ?1", params![quiz_id]).unwrap();
+        assert!(load_quiz(&conn, quiz_id).is_err());
+
+        let entries: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM notebook_entries WHERE entry_type = 'quiz' AND entry_id = ?1",
+            params![quiz_id],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn test_score_report_groups_by_video_and_tag() {
+        let conn = open_test_db();
+        let first = insert_test_video(&conn, "vid1", "Video vid1", "Onboarding");
+        let second = insert_test_video(&conn, "vid2", "Video vid2", "Platform");
+        let quiz_a = insert_quiz(&conn, first, &[question(0), question(1)], None, None).unwrap();
+        let quiz_b = insert_quiz(&conn, second, &[question(0)], None, None).unwrap();
+        conn.execute(
+            "UPDATE notebook_entries SET tags = 'Platform, deploys' WHERE entry_type = 'quiz' AND entry_id = ?1",
+            params![quiz_b],
+        ).unwrap();
+
+        record_attempt(&conn, quiz_a, &[Some(0), Some(0)], Some("sam")).unwrap();
+        record_attempt(&conn, quiz_a, &[Some(0), Some(1)], Some("sam")).unwrap();
+        record_attempt(&conn, quiz_b, &[Some(0)], Some("alex")).unwrap();
+
+        let report = load_score_report(&conn, None).unwrap();
+        assert_eq!(report.by_video.len(), 2);
+        let vid1 = &report.by_video[0];
+        assert_eq!((vid1.key.as_str(), vid1.attempts), ("vid1", 2));
+        assert_eq!((vid1.average_score, vid1.best_score, vid1.latest_score), (75.0, 100.0, 100.0));
+
+        let tags: Vec<&str> = report.by_tag.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(tags, vec!["Onboarding", "Platform", "deploys"]);
+
+        let report = load_score_report(&conn, Some("alex")).unwrap();
+        assert_eq!(report.by_video.len(), 1);
+        assert_eq!(report.by_video[0].history[0].quiz_id, quiz_b);
+
+        let tx = conn.unchecked_transaction().unwrap();
+        delete_quiz_rows(&tx, quiz_a).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(load_score_report(&conn, Some("sam")).unwrap().by_video.len(), 0);
+    }
+}
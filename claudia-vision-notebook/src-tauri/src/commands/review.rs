@@ -0,0 +1,467 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+use crate::anki::{escape_html, write_package, AnkiNote};
+use crate::commands::agents::AgentDb;
+use crate::commands::quiz::{check_answers, load_quiz};
+use crate::commands::vision_notebook::{ensure_history_exists, source_link, QuizQuestion};
+use crate::manifest::Manifest;
+
+/// Ease factor new cards start with
+const INITIAL_EASE: f64 = 2.5;
+/// SM-2 never lets ease drop below this
+const MIN_EASE: f64 = 1.3;
+
+const DEFAULT_DECK_NAME: &str = "Vision Notebook";
+
+/// A quiz question scheduled for review
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReviewCard {
+    pub id: i64,
+    pub history_id: i64,
+    pub quiz_id: Option<i64>,
+    pub video_id: String,
+    pub video_title: String,
+    pub question: QuizQuestion,
+    /// Seconds into the video where the answer is discussed
+    pub timestamp: Option<f64>,
+    /// Link that opens the video at `timestamp`
+    pub source_url: String,
+    pub ease: f64,
+    pub interval_days: i64,
+    pub repetitions: i64,
+    pub lapses: i64,
+    pub due_at: String,
+    pub last_reviewed_at: Option<String>,
+}
+
+/// SM-2 scheduling state of a card
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Schedule {
+    ease: f64,
+    interval_days: i64,
+    repetitions: i64,
+}
+
+impl Schedule {
+    /// Apply a review graded 0 (blackout) to 5 (perfect recall)
+    ///
+    /// Grades below 3 restart the card at a one-day interval.
+    fn grade(self, quality: u8) -> Self {
+        let q = quality.min(5) as f64;
+        let ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+
+        if quality < 3 {
+            return Self { ease, interval_days: 1, repetitions: 0 };
+        }
+
+        let repetitions = self.repetitions + 1;
+        let interval_days = match repetitions {
+            1 => 1,
+            2 => 6,
+            _ => (self.interval_days as f64 * self.ease).round() as i64,
+        };
+        Self { ease, interval_days, repetitions }
+    }
+}
+
+/// Turn quiz questions into review cards; questions already on the video are skipped
+#[command]
+pub async fn create_review_cards(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    questions: Vec<QuizQuestion>,
+    quiz_id: Option<i64>,
+) -> Result<Vec<ReviewCard>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let ids = insert_cards(&conn, history_id, quiz_id, &questions)?;
+    ids.into_iter().map(|id| load_card(&conn, id)).collect()
+}
+
+/// Cards due for review now, oldest first
+#[command]
+pub async fn get_due_review_cards(
+    db: State<'_, AgentDb>,
+    history_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<ReviewCard>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_due_cards(&conn, history_id, limit.unwrap_or(20))
+}
+
+/// Record a review graded 0-5 and schedule the card's next review
+#[command]
+pub async fn grade_review_card(
+    db: State<'_, AgentDb>,
+    card_id: i64,
+    quality: u8,
+) -> Result<ReviewCard, String> {
+    if quality > 5 {
+        return Err("Grade must be between 0 and 5".to_string());
+    }
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    apply_grade(&conn, card_id, quality)?;
+    load_card(&conn, card_id)
+}
+
+/// Remove a card from the review queue
+#[command]
+pub async fn delete_review_card(
+    db: State<'_, AgentDb>,
+    card_id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM review_cards WHERE id = ?1", params![card_id])
+        .map_err(|e| format!("Failed to delete review card: {}", e))?;
+    Ok(())
+}
+
+/// Export quizzes and review cards as an Anki package
+///
+/// Each question becomes one card showing the frame on screen at its
+/// timestamp, with the video title, channel and a link back to that moment.
+/// Returns the number of cards written.
+#[command]
+pub async fn export_anki_deck(
+    db: State<'_, AgentDb>,
+    output_path: String,
+    deck_name: Option<String>,
+    quiz_ids: Vec<i64>,
+    card_ids: Vec<i64>,
+    include_frames: Option<bool>,
+) -> Result<usize, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut questions = Vec::new();
+    for quiz_id in quiz_ids {
+        let quiz = load_quiz(&conn, quiz_id)?;
+        questions.extend(quiz.questions.into_iter().map(|question| (quiz.history_id, question)));
+    }
+    for card_id in card_ids {
+        let card = load_card(&conn, card_id)?;
+        questions.push((card.history_id, card.question));
+    }
+
+    let notes = build_anki_notes(&conn, &questions, include_frames.unwrap_or(true))?;
+    if notes.is_empty() {
+        return Err("No questions to export".to_string());
+    }
+
+    let deck_name = deck_name
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_DECK_NAME.to_string());
+    write_package(Path::new(&output_path), &deck_name, &notes)?;
+
+    Ok(notes.len())
+}
+
+// Helper functions
+
+fn insert_cards(
+    conn: &Connection,
+    history_id: i64,
+    quiz_id: Option<i64>,
+    questions: &[QuizQuestion],
+) -> Result<Vec<i64>, String> {
+    ensure_history_exists(conn, history_id)?;
+    check_answers(questions)?;
+    let mut ids = Vec::new();
+    for question in questions {
+        let question_json = serde_json::to_string(question)
+            .map_err(|e| format!("Failed to serialize question: {}", e))?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO review_cards
+             (history_id, quiz_id, question_text, question, timestamp, ease)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![history_id, quiz_id, question.question.trim(), &question_json, question.timestamp, INITIAL_EASE],
+        ).map_err(|e| format!("Failed to create review card: {}", e))?;
+
+        if inserted > 0 {
+            ids.push(conn.last_insert_rowid());
+        }
+    }
+    Ok(ids)
+}
+
+fn apply_grade(conn: &Connection, card_id: i64, quality: u8) -> Result<(), String> {
+    let schedule = conn.query_row(
+        "SELECT ease, interval_days, repetitions FROM review_cards WHERE id = ?1",
+        params![card_id],
+        |row| Ok(Schedule { ease: row.get(0)?, interval_days: row.get(1)?, repetitions: row.get(2)? }),
+    ).map_err(|e| format!("Failed to get review card: {}", e))?;
+
+    let next = schedule.grade(quality);
+    conn.execute(
+        "UPDATE review_cards
+         SET ease = ?1, interval_days = ?2, repetitions = ?3,
+             lapses = lapses + ?4,
+             due_at = datetime('now', '+' || ?2 || ' days'),
+             last_reviewed_at = datetime('now')
+         WHERE id = ?5",
+        params![next.ease, next.interval_days, next.repetitions, (quality < 3) as i64, card_id],
+    ).map_err(|e| format!("Failed to grade review card: {}", e))?;
+
+    Ok(())
+}
+
+/// Video details shared by every card exported from one history entry
+struct NoteSource {
+    video_id: String,
+    title: String,
+    channel: String,
+    video_url: String,
+    source_type: String,
+    manifest: Option<Manifest>,
+}
+
+/// One Anki note per distinct question, in the order given
+fn build_anki_notes(
+    conn: &Connection,
+    questions: &[(i64, QuizQuestion)],
+    include_frames: bool,
+) -> Result<Vec<AnkiNote>, String> {
+    let mut sources: HashMap<i64, NoteSource> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut notes = Vec::new();
+
+    for (history_id, question) in questions {
+        if !sources.contains_key(history_id) {
+            sources.insert(*history_id, load_note_source(conn, *history_id)?);
+        }
+        let source = &sources[history_id];
+
+        // The same question exported again keeps its guid, so Anki updates the note
+        let digest = Sha256::digest(format!("{}\x1f{}", source.video_id, question.question.trim()).as_bytes());
+        let guid = format!("{:x}", digest)[..20].to_string();
+        if !seen.insert(guid.clone()) {
+            continue;
+        }
+
+        let t = question.timestamp.unwrap_or(0.0);
+        let image = source.manifest.as_ref()
+            .filter(|_| include_frames)
+            .and_then(|manifest| manifest.frame_at(t))
+            .map(PathBuf::from)
+            .filter(|path| path.exists());
+
+        let options: String = question.options.iter()
+            .map(|option| format!("<li>{}</li>", escape_html(option)))
+            .collect();
+        let front = format!("{}<ol type=\"A\">{}</ol>", escape_html(&question.question), options);
+
+        let correct = question.options.get(question.correct_answer).map(String::as_str).unwrap_or_default();
+        let mut back = format!("<b>{}. {}</b>", option_letter(question.correct_answer), escape_html(correct));
+        if !question.explanation.trim().is_empty() {
+            back.push_str(&format!("<br>{}", escape_html(&question.explanation)));
+        }
+
+        notes.push(AnkiNote {
+            guid,
+            front,
+            back,
+            image,
+            title: source.title.clone(),
+            channel: source.channel.clone(),
+            link: source_link(&source.source_type, &source.video_id, &source.video_url, t),
+        });
+    }
+
+    Ok(notes)
+}
+
+/// `A` for the first option, `B` for the second and so on; `?` past `Z`
+fn option_letter(index: usize) -> char {
+    u32::try_from(index)
+        .ok()
+        .filter(|index| *index < 26)
+        .and_then(|index| char::from_u32('A' as u32 + index))
+        .unwrap_or('?')
+}
+
+fn load_note_source(conn: &Connection, history_id: i64) -> Result<NoteSource, String> {
+    let (source, manifest_path) = conn.query_row(
+        "SELECT video_id, title, channel, video_url, source_type, manifest_path
+         FROM transcript_history WHERE id = ?1",
+        params![history_id],
+        |row| Ok((
+            NoteSource {
+                video_id: row.get(0)?,
+                title: row.get(1)?,
+                channel: row.get(2)?,
+                video_url: row.get(3)?,
+                source_type: row.get(4)?,
+                manifest: None,
+            },
+            row.get::<_, Option<String>>(5)?,
+        )),
+    ).map_err(|e| format!("Failed to get transcript details: {}", e))?;
+
+    // Cards still export without frames when the manifest is gone
+    let manifest = manifest_path
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+        .and_then(|path| Manifest::load(&path).ok());
+
+    Ok(NoteSource { manifest, ..source })
+}
+
+const CARD_COLUMNS: &str =
+    "c.id, c.history_id, c.quiz_id, th.video_id, th.title, th.video_url, th.source_type,
+     c.question, c.timestamp, c.ease, c.interval_days, c.repetitions, c.lapses, c.due_at, c.last_reviewed_at";
+
+fn card_from_row(row: &rusqlite::Row) -> rusqlite::Result<ReviewCard> {
+    let video_id: String = row.get(3)?;
+    let video_url: String = row.get(5)?;
+    let source_type: String = row.get(6)?;
+    let question_json: String = row.get(7)?;
+    let timestamp: Option<f64> = row.get(8)?;
+
+    let question: QuizQuestion = serde_json::from_str(&question_json)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?;
+
+    Ok(ReviewCard {
+        id: row.get(0)?,
+        history_id: row.get(1)?,
+        quiz_id: row.get(2)?,
+        source_url: source_link(&source_type, &video_id, &video_url, timestamp.unwrap_or(0.0)),
+        video_id,
+        video_title: row.get(4)?,
+        question,
+        timestamp,
+        ease: row.get(9)?,
+        interval_days: row.get(10)?,
+        repetitions: row.get(11)?,
+        lapses: row.get(12)?,
+        due_at: row.get(13)?,
+        last_reviewed_at: row.get(14)?,
+    })
+}
+
+fn load_card(conn: &Connection, card_id: i64) -> Result<ReviewCard, String> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM review_cards c
+             JOIN transcript_history th ON th.id = c.history_id
+             WHERE c.id = ?1",
+            CARD_COLUMNS
+        ),
+        params![card_id],
+        card_from_row,
+    ).map_err(|e| format!("Failed to get review card: {}", e))
+}
+
+fn load_due_cards(conn: &Connection, history_id: Option<i64>, limit: i64) -> Result<Vec<ReviewCard>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM review_cards c
+         JOIN transcript_history th ON th.id = c.history_id
+         WHERE c.due_at <= datetime('now') AND (?1 IS NULL OR c.history_id = ?1)
+         ORDER BY c.due_at, c.id
+         LIMIT ?2",
+        CARD_COLUMNS
+    )).map_err(|e| e.to_string())?;
+
+    let cards = stmt.query_map(params![history_id, limit], card_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    fn question(text: &str, timestamp: f64) -> QuizQuestion {
+        QuizQuestion {
+            question: text.to_string(),
+            options: vec!["a".into(), "b".into(), "c".into(), "d".into()],
+            correct_answer: 1,
+            explanation: String::new(),
+            timestamp: Some(timestamp),
+        }
+    }
+
+    #[test]
+    fn test_sm2_schedule() {
+        let new = Schedule { ease: INITIAL_EASE, interval_days: 0, repetitions: 0 };
+
+        let first = new.grade(4);
+        assert_eq!((first.interval_days, first.repetitions), (1, 1));
+        assert!((first.ease - 2.5).abs() < 1e-9);
+
+        let second = first.grade(5);
+        assert_eq!((second.interval_days, second.repetitions), (6, 2));
+        assert!((second.ease - 2.6).abs() < 1e-9);
+
+        let third = second.grade(3);
+        assert_eq!(third.interval_days, 16); // 6 * 2.6
+        assert!((third.ease - 2.46).abs() < 1e-9);
+
+        let lapsed = third.grade(1);
+        assert_eq!((lapsed.interval_days, lapsed.repetitions), (1, 0));
+        assert!((lapsed.ease - 1.92).abs() < 1e-9);
+
+        let floor = Schedule { ease: MIN_EASE, interval_days: 1, repetitions: 0 }.grade(0);
+        assert_eq!(floor.ease, MIN_EASE);
+    }
+
+    #[test]
+    fn test_cards_leave_queue_once_graded() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "dQw4w9WgXcQ", "Ownership", "Crab Academy");
+
+        let ids = insert_cards(&conn, history_id, None, &[question("What moves?", 95.7), question("Who borrows?", 300.0)]).unwrap();
+        assert_eq!(ids.len(), 2);
+        // The same question is not added twice
+        assert!(insert_cards(&conn, history_id, None, &[question("What moves?", 95.7)]).unwrap().is_empty());
+        let mut unanswerable = question("Which?", 10.0);
+        unanswerable.correct_answer = 200;
+        assert!(insert_cards(&conn, history_id, None, &[unanswerable]).is_err());
+
+        let due = load_due_cards(&conn, Some(history_id), 10).unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].source_url, "https://youtu.be/dQw4w9WgXcQ?t=95");
+
+        apply_grade(&conn, ids[0], 5).unwrap();
+        let due = load_due_cards(&conn, None, 10).unwrap();
+        assert_eq!(due.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[1]]);
+
+        let graded = load_card(&conn, ids[0]).unwrap();
+        assert_eq!((graded.interval_days, graded.repetitions, graded.lapses), (1, 1, 0));
+        assert!(graded.last_reviewed_at.is_some());
+    }
+
+    #[test]
+    fn test_anki_notes_from_questions() {
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "dQw4w9WgXcQ", "Ownership", "Crab Academy");
+
+        let mut tricky = question("Is 1 < 2 & 3?", 300.0);
+        tricky.explanation = "Comparison binds tighter.".to_string();
+        let questions = vec![
+            (history_id, question("What moves?", 95.7)),
+            (history_id, tricky),
+            // Exported from both a quiz and a review card
+            (history_id, question("What moves? ", 95.7)),
+        ];
+
+        let notes = build_anki_notes(&conn, &questions, true).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].link, "https://youtu.be/dQw4w9WgXcQ?t=95");
+        assert_eq!((notes[0].title.as_str(), notes[0].channel.as_str()), ("Ownership", "Crab Academy"));
+        assert!(notes[0].image.is_none());
+        assert_eq!(notes[1].front, "Is 1 &lt; 2 &amp; 3?<ol type=\"A\"><li>a</li><li>b</li><li>c</li><li>d</li></ol>");
+        assert_eq!(notes[1].back, "<b>B. b</b><br>Comparison binds tighter.");
+
+        assert_eq!((option_letter(0), option_letter(25), option_letter(26), option_letter(usize::MAX)), ('A', 'Z', '?', '?'));
+
+        // Guids are stable across exports
+        let again = build_anki_notes(&conn, &questions[..1], true).unwrap();
+        assert_eq!(again[0].guid, notes[0].guid);
+    }
+}
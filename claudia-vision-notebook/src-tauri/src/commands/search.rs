@@ -0,0 +1,214 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::vision_notebook::{source_link, TranscriptSegment};
+
+/// Words of context on each side of a match in a snippet
+const SNIPPET_TOKENS: i64 = 16;
+
+/// A transcript segment matching a search
+#[derive(Debug, Serialize)]
+pub struct SegmentSearchHit {
+    pub history_id: i64,
+    pub video_id: String,
+    pub title: String,
+    pub channel: String,
+    pub source_type: String,
+    /// Seconds from the start of the video
+    pub start: f64,
+    pub duration: f64,
+    /// Segment text with matches wrapped in `<mark>`
+    pub snippet: String,
+    /// bm25 relevance; higher is better
+    pub score: f64,
+    /// Link that opens the video at `start`
+    pub link: String,
+}
+
+/// Search transcript segments and return the moments that match, best first
+///
+/// `history_id` limits the search to one video.
+#[command]
+pub async fn search_transcript_segments(
+    db: State<'_, AgentDb>,
+    query: String,
+    history_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<SegmentSearchHit>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    search_segments(&conn, &query, history_id, limit.unwrap_or(50))
+}
+
+// Helper functions
+
+/// Replace the indexed segments of a history entry
+pub(crate) fn index_segments(
+    conn: &Connection,
+    history_id: i64,
+    segments: &[TranscriptSegment],
+) -> Result<(), String> {
+    remove_segments(conn, history_id)?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO transcript_segments (history_id, position, start, duration, text)
+         VALUES (?1, ?2, ?3, ?4, ?5)"
+    ).map_err(|e| e.to_string())?;
+
+    for (position, segment) in segments.iter().enumerate() {
+        stmt.execute(params![history_id, position as i64, segment.start, segment.duration, &segment.text])
+            .map_err(|e| format!("Failed to index transcript: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Drop a history entry's segments from the index
+pub(crate) fn remove_segments(conn: &Connection, history_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM transcript_segments WHERE history_id = ?1", params![history_id])
+        .map_err(|e| format!("Failed to remove transcript from index: {}", e))?;
+    Ok(())
+}
+
+/// Turn user input into an FTS5 query that cannot fail to parse
+///
+/// Each word becomes a quoted term and all must match. A trailing `*` keeps
+/// prefix matching. Returns None when there is nothing to search for.
+pub(crate) fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .filter_map(|word| {
+            let prefix = word.ends_with('*');
+            let word = word.trim_end_matches('*');
+            // Bare punctuation tokenizes to nothing and would match nothing
+            if !word.chars().any(char::is_alphanumeric) {
+                return None;
+            }
+            let quoted = format!("\"{}\"", word.replace('"', "\"\""));
+            Some(if prefix { format!("{}*", quoted) } else { quoted })
+        })
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn search_segments(
+    conn: &Connection,
+    query: &str,
+    history_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<SegmentSearchHit>, String> {
+    let Some(fts) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT s.history_id, th.video_id, th.title, th.channel, th.source_type, th.video_url,
+                s.start, s.duration,
+                snippet(transcript_segments_fts, 0, '<mark>', '</mark>', '...', ?2),
+                bm25(transcript_segments_fts) AS score
+         FROM transcript_segments_fts
+         JOIN transcript_segments s ON s.id = transcript_segments_fts.rowid
+         JOIN transcript_history th ON th.id = s.history_id
+         WHERE transcript_segments_fts MATCH ?1
+         AND (?3 IS NULL OR s.history_id = ?3)
+         ORDER BY score, s.history_id, s.start
+         LIMIT ?4"
+    ).map_err(|e| e.to_string())?;
+
+    let hits = stmt.query_map(params![&fts, SNIPPET_TOKENS, history_id, limit], |row| {
+        let video_id: String = row.get(1)?;
+        let source_type: String = row.get(4)?;
+        let video_url: String = row.get(5)?;
+        let start: f64 = row.get(6)?;
+        Ok(SegmentSearchHit {
+            history_id: row.get(0)?,
+            link: source_link(&source_type, &video_id, &video_url, start),
+            video_id,
+            title: row.get(2)?,
+            channel: row.get(3)?,
+            source_type,
+            start,
+            duration: row.get(7)?,
+            snippet: row.get(8)?,
+            // bm25() is lower for better matches
+            score: -row.get::<_, f64>(9)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    fn insert_video(conn: &Connection, video_id: &str, lines: &[&str]) -> i64 {
+        let segments: Vec<TranscriptSegment> = lines
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TranscriptSegment { text: text.to_string(), start: i as f64 * 10.0 + 0.5, duration: 10.0 })
+            .collect();
+        let history_id = insert_test_video(conn, video_id, video_id, "Crab Academy");
+        index_segments(conn, history_id, &segments).unwrap();
+        history_id
+    }
+
+    #[test]
+    fn test_fts_query_quotes_terms() {
+        assert_eq!(fts_query("borrow checker").as_deref(), Some("\"borrow\" \"checker\""));
+        assert_eq!(fts_query("don't \"panic\" life*").as_deref(), Some("\"don't\" \"\"\"panic\"\"\" \"life\"*"));
+        assert_eq!(fts_query("  -- * "), None);
+    }
+
+    #[test]
+    fn test_search_ranks_segments_and_links_to_moment() {
+        let conn = open_test_db();
+        let rust = insert_video(&conn, "rustRUST001", &[
+            "welcome to the talk",
+            "the borrow checker rejects this code",
+            "borrow borrow borrow: the borrow checker again",
+        ]);
+        insert_video(&conn, "goGOGOGO002", &["goroutines do not borrow anything"]);
+
+        let hits = search_segments(&conn, "borrow checker", None, 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.history_id == rust));
+        assert_eq!(hits[0].start, 20.5);
+        assert!(hits[0].score >= hits[1].score);
+        assert_eq!(hits[1].snippet, "the <mark>borrow</mark> <mark>checker</mark> rejects this code");
+        assert_eq!(hits[1].link, "https://youtu.be/rustRUST001?t=10");
+
+        assert_eq!(search_segments(&conn, "borrow", None, 10).unwrap().len(), 3);
+        // Scoped to one video
+        assert!(search_segments(&conn, "goroutines", Some(rust), 10).unwrap().is_empty());
+
+        // Reindexing replaces the old segments
+        index_segments(&conn, rust, &[]).unwrap();
+        assert_eq!(search_segments(&conn, "borrow", None, 10).unwrap().len(), 1);
+        assert!(search_segments(&conn, "\"", None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migration_indexes_existing_transcripts() {
+        // A transcript stored before the segment index existed
+        let conn = open_test_db();
+        let history_id = insert_test_video(&conn, "oldOLDold01", "Old", "Crab Academy");
+        conn.execute(
+            "UPDATE transcript_history
+             SET transcript_length = 1,
+                 transcript_text = '[{\"text\": \"lifetimes explained\", \"start\": 42.0, \"duration\": 3.0}]'
+             WHERE id = ?1",
+            params![history_id],
+        ).unwrap();
+        conn.execute("DELETE FROM schema_version WHERE version >= 10", []).unwrap();
+        crate::commands::migrations::run_migrations(&conn).unwrap();
+
+        let hits = search_segments(&conn, "lifetimes", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].start, 42.0);
+    }
+}
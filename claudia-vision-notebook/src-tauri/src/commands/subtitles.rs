@@ -0,0 +1,114 @@
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::library::project_dir_path;
+use crate::commands::vision_notebook::{
+    load_transcript_details, local_video_id,
+    save_transcript_history, ProcessVideoResult, VideoInfo,
+};
+use crate::manifest::Manifest;
+use crate::transcript::subtitles::{parse_subtitles, render_subtitles, CueOptions, SubtitleFormat};
+use crate::transcript::youtube::iso8601_duration;
+
+/// Source type recorded on `transcript_history` for imported subtitle files
+const SUBTITLE_SOURCE_TYPE: &str = "subtitle_file";
+
+/// Export a stored transcript as subtitles or timestamped text
+///
+/// If `output_path` is an existing directory the file is named after the video id.
+#[command]
+pub async fn export_transcript_subtitles(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    format: SubtitleFormat,
+    output_path: String,
+    options: Option<CueOptions>,
+) -> Result<String, String> {
+    let details = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_transcript_details(&conn, history_id)?
+    };
+    
+    let content = render_subtitles(&details.transcript, format, &options.unwrap_or_default());
+    
+    let mut path = PathBuf::from(&output_path);
+    if path.is_dir() {
+        path = path.join(format!("{}.{}", details.video_id, format.extension()));
+    }
+    
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write subtitles: {}", e))?;
+    
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Import a local SRT or WebVTT file as a notebook transcript
+///
+/// The transcript gets a synthetic `local-…` video id derived from the file
+/// contents, so importing the same file again replaces the earlier import.
+#[command]
+pub async fn import_subtitle_file(
+    db: State<'_, AgentDb>,
+    file_path: String,
+    project_dir: Option<String>,
+    title: Option<String>,
+) -> Result<ProcessVideoResult, String> {
+    let path = Path::new(&file_path);
+    match path.extension().and_then(|e| e.to_str()).and_then(SubtitleFormat::from_extension) {
+        Some(SubtitleFormat::Srt) | Some(SubtitleFormat::Vtt) => {}
+        _ => return Err("Only .srt and .vtt subtitle files can be imported".to_string()),
+    }
+    
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read subtitle file: {}", e))?;
+    let segments = parse_subtitles(&content)?;
+    
+    let video_id = local_video_id(&content);
+    let title = title.unwrap_or_else(|| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Imported transcript")
+            .to_string()
+    });
+    let end = segments.iter().map(|s| s.start + s.duration).fold(0.0, f64::max);
+    
+    let video_info = VideoInfo {
+        video_id: video_id.clone(),
+        title,
+        channel: "Local import".to_string(),
+        duration: Some(iso8601_duration(end.ceil() as u64)),
+        thumbnail_url: None,
+    };
+    
+    let video_dir = project_dir_path(&db, project_dir.as_deref())?.join(&video_id);
+    std::fs::create_dir_all(&video_dir)
+        .map_err(|e| format!("Failed to create video directory: {}", e))?;
+    
+    let manifest = Manifest::build(&video_info, &segments, &[], Some(SUBTITLE_SOURCE_TYPE), None);
+    let manifest_path = video_dir.join("manifest.json");
+    manifest.save(&manifest_path)?;
+    
+    let source_url = format!("file://{}", path.canonicalize().unwrap_or_else(|_| path.to_path_buf()).display());
+    
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        save_transcript_history(
+            &conn,
+            &video_info,
+            &source_url,
+            &segments,
+            &manifest_path,
+            Some(SUBTITLE_SOURCE_TYPE),
+            SUBTITLE_SOURCE_TYPE,
+        )?;
+    }
+    
+    Ok(ProcessVideoResult {
+        success: true,
+        video_info: Some(video_info),
+        transcript: Some(segments),
+        manifest_path: Some(manifest_path.to_string_lossy().to_string()),
+        provider: Some(SUBTITLE_SOURCE_TYPE.to_string()),
+        error: None,
+    })
+}
@@ -0,0 +1,339 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::history::delete_notebook_entries;
+use crate::commands::search::index_segments;
+use crate::commands::vision_notebook::{
+    ensure_history_exists, extract_video_id, index_transcript_entry, load_provider_order,
+    load_transcript_details, TranscriptSegment, VideoInfo,
+};
+use crate::manifest::Manifest;
+use crate::transcript::youtube::{self, CaptionTrack};
+use crate::transcript::{FetchedTranscript, TranscriptProviderState};
+
+/// Language code stored for tracks whose provider did not report one
+const UNKNOWN_LANGUAGE: &str = "und";
+
+/// A transcript language stored for a video
+#[derive(Debug, Serialize)]
+pub struct TranscriptTrackInfo {
+    pub language_code: String,
+    pub is_auto_generated: bool,
+    /// Transcribed locally from the audio
+    pub is_machine_transcribed: bool,
+    pub provider: Option<String>,
+    pub transcript_length: i64,
+    /// Whether this is the track in `transcript_history` and the manifest
+    pub is_primary: bool,
+    pub fetched_at: String,
+}
+
+/// List the caption languages YouTube offers for a video
+#[command]
+pub async fn list_caption_languages(url: String) -> Result<Vec<CaptionTrack>, String> {
+    let video_id = extract_video_id(url).await?;
+    youtube::list_caption_tracks(&video_id).await
+}
+
+/// List the transcript tracks stored for a history entry
+#[command]
+pub async fn list_transcript_tracks(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+) -> Result<Vec<TranscriptTrackInfo>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_track_infos(&conn, history_id)
+}
+
+/// Fetch another caption language for a stored YouTube video
+#[command]
+pub async fn add_transcript_track(
+    db: State<'_, AgentDb>,
+    providers: State<'_, TranscriptProviderState>,
+    history_id: i64,
+    language: String,
+) -> Result<TranscriptTrackInfo, String> {
+    let (video_id, source_type, provider_order) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let (video_id, source_type) = conn.query_row(
+            "SELECT video_id, source_type FROM transcript_history WHERE id = ?1",
+            params![history_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        ).map_err(|e| format!("Failed to get transcript details: {}", e))?;
+        (video_id, source_type, load_provider_order(&conn))
+    };
+
+    if source_type != "youtube" {
+        return Err("Additional caption tracks can only be fetched for YouTube videos".to_string());
+    }
+
+    let fetched = providers.0.fetch(&video_id, Some(&language), &provider_order).await?;
+
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let language_code = save_transcript_track(&conn, history_id, &fetched)?;
+
+    load_track_infos(&conn, history_id)?
+        .into_iter()
+        .find(|track| track.language_code == language_code)
+        .ok_or_else(|| "Failed to save transcript track".to_string())
+}
+
+/// Make a stored track the primary transcript and rewrite the manifest text from it
+#[command]
+pub async fn set_primary_transcript_track(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    language: String,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let details = load_transcript_details(&conn, history_id)?;
+    let segments = load_track_segments(&conn, history_id, Some(&language))?;
+    let track: Option<(String, Option<String>, bool)> = conn.query_row(
+        "SELECT language_code, provider, is_machine_transcribed FROM transcript_tracks
+         WHERE history_id = ?1 AND language_code = ?2 COLLATE NOCASE",
+        params![history_id, &language],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional().map_err(|e| e.to_string())?;
+    let (language_code, provider, is_machine_transcribed) = track.unwrap_or_else(|| {
+        (
+            details.language_code.clone().unwrap_or(language),
+            details.provider.clone(),
+            details.is_machine_transcribed,
+        )
+    });
+
+    // Keep the sampled frames and swap the transcript text around them
+    if let Some(manifest_path) = details.manifest_path.as_deref().map(Path::new) {
+        if manifest_path.exists() {
+            let mut manifest = Manifest::load(manifest_path)?;
+            manifest.replace_transcript(&segments, provider.as_deref());
+            manifest.save(manifest_path)?;
+        }
+    }
+
+    let transcript_json = serde_json::to_string(&segments)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    conn.execute(
+        "UPDATE transcript_history
+         SET transcript_text = ?1, transcript_length = ?2, language_code = ?3, provider = ?4,
+             is_machine_transcribed = ?5, cleaned_transcript = NULL
+         WHERE id = ?6",
+        params![
+            &transcript_json,
+            segments.len() as i64,
+            &language_code,
+            &provider,
+            is_machine_transcribed,
+            history_id
+        ],
+    ).map_err(|e| format!("Failed to update transcript history: {}", e))?;
+    index_segments(&conn, history_id, &segments)?;
+
+    // The notebook preview follows the primary track
+    delete_notebook_entries(&conn, "transcript", history_id)
+        .map_err(|e| format!("Failed to replace notebook entry: {}", e))?;
+    let video_info = VideoInfo {
+        video_id: details.video_id,
+        title: details.title,
+        channel: details.channel,
+        duration: details.duration,
+        thumbnail_url: details.thumbnail_url,
+    };
+    index_transcript_entry(&conn, history_id, &video_info, &segments)
+}
+
+// Helper functions
+
+/// Insert or replace a fetched track for a history entry and return its language code
+pub(crate) fn save_transcript_track(
+    conn: &rusqlite::Connection,
+    history_id: i64,
+    fetched: &FetchedTranscript,
+) -> Result<String, String> {
+    ensure_history_exists(conn, history_id)?;
+    let language_code = fetched.language.clone().unwrap_or_else(|| UNKNOWN_LANGUAGE.to_string());
+    let transcript_json = serde_json::to_string(&fetched.segments)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO transcript_tracks
+         (history_id, language_code, is_auto_generated, is_machine_transcribed, provider,
+          transcript_length, transcript_text)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(history_id, language_code) DO UPDATE SET
+             is_auto_generated = excluded.is_auto_generated,
+             is_machine_transcribed = excluded.is_machine_transcribed,
+             provider = excluded.provider,
+             transcript_length = excluded.transcript_length,
+             transcript_text = excluded.transcript_text,
+             fetched_at = CURRENT_TIMESTAMP",
+        params![
+            history_id,
+            &language_code,
+            fetched.is_auto_generated,
+            fetched.is_machine_transcribed,
+            &fetched.provider,
+            fetched.segments.len() as i64,
+            &transcript_json
+        ],
+    ).map_err(|e| format!("Failed to save transcript track: {}", e))?;
+
+    Ok(language_code)
+}
+
+/// Segments of a stored track, or of the primary transcript when `language` is None
+pub(crate) fn load_track_segments(
+    conn: &rusqlite::Connection,
+    history_id: i64,
+    language: Option<&str>,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let Some(language) = language else {
+        return Ok(load_transcript_details(conn, history_id)?.transcript);
+    };
+
+    let transcript_json: Option<String> = conn.query_row(
+        "SELECT transcript_text FROM transcript_tracks
+         WHERE history_id = ?1 AND language_code = ?2 COLLATE NOCASE",
+        params![history_id, language],
+        |row| row.get(0),
+    ).optional().map_err(|e| e.to_string())?;
+
+    match transcript_json {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse transcript track: {}", e)),
+        None => {
+            // Entries saved before tracks existed only have their primary transcript
+            let details = load_transcript_details(conn, history_id)?;
+            match &details.language_code {
+                Some(code) if code.eq_ignore_ascii_case(language) => Ok(details.transcript),
+                _ => Err(format!("No {} transcript stored for this video", language)),
+            }
+        }
+    }
+}
+
+fn load_track_infos(conn: &rusqlite::Connection, history_id: i64) -> Result<Vec<TranscriptTrackInfo>, String> {
+    let (primary_language, provider, transcript_length, fetched_at, is_machine_transcribed):
+        (Option<String>, Option<String>, i64, String, bool) =
+        conn.query_row(
+            "SELECT language_code, provider, transcript_length, fetched_at, is_machine_transcribed
+             FROM transcript_history WHERE id = ?1",
+            params![history_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        ).map_err(|e| format!("Failed to get transcript details: {}", e))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT language_code, is_auto_generated, provider, transcript_length, fetched_at,
+                is_machine_transcribed
+         FROM transcript_tracks
+         WHERE history_id = ?1
+         ORDER BY language_code"
+    ).map_err(|e| e.to_string())?;
+
+    let mut tracks = stmt.query_map(params![history_id], |row| {
+        let language_code: String = row.get(0)?;
+        Ok(TranscriptTrackInfo {
+            is_primary: primary_language.as_deref() == Some(language_code.as_str()),
+            language_code,
+            is_auto_generated: row.get(1)?,
+            is_machine_transcribed: row.get(5)?,
+            provider: row.get(2)?,
+            transcript_length: row.get(3)?,
+            fetched_at: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Entries saved before tracks existed still have their primary transcript
+    if !tracks.iter().any(|track| track.is_primary) {
+        tracks.insert(0, TranscriptTrackInfo {
+            language_code: primary_language.unwrap_or_else(|| UNKNOWN_LANGUAGE.to_string()),
+            is_auto_generated: false,
+            is_machine_transcribed,
+            provider,
+            transcript_length,
+            is_primary: true,
+            fetched_at,
+        });
+    }
+
+    Ok(tracks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::{insert_test_video, open_test_db};
+
+    fn segments(text: &str) -> Vec<TranscriptSegment> {
+        vec![TranscriptSegment { text: text.to_string(), start: 1.5, duration: 2.0 }]
+    }
+
+    fn fetched(language: Option<&str>, text: &str) -> FetchedTranscript {
+        FetchedTranscript {
+            video_info: VideoInfo {
+                video_id: "abcDEF12345".to_string(),
+                title: "Ownership".to_string(),
+                channel: "Crab Academy".to_string(),
+                duration: None,
+                thumbnail_url: None,
+            },
+            segments: segments(text),
+            language: language.map(str::to_string),
+            is_auto_generated: true,
+            is_machine_transcribed: false,
+            chapters: Vec::new(),
+            provider: "youtube_captions".to_string(),
+        }
+    }
+
+    /// A video whose English transcript was saved before tracks existed
+    fn insert_english_video(conn: &rusqlite::Connection) -> i64 {
+        let history_id = insert_test_video(conn, "abcDEF12345", "Ownership", "Crab Academy");
+        conn.execute(
+            "UPDATE transcript_history SET language_code = 'en', transcript_length = 1, transcript_text = ?1
+             WHERE id = ?2",
+            params![serde_json::to_string(&segments("hello")).unwrap(), history_id],
+        ).unwrap();
+        history_id
+    }
+
+    #[test]
+    fn test_tracks_round_trip() {
+        let conn = open_test_db();
+        let history_id = insert_english_video(&conn);
+
+        assert_eq!(save_transcript_track(&conn, history_id, &fetched(Some("fr"), "bonjour")).unwrap(), "fr");
+        let french = load_track_segments(&conn, history_id, Some("FR")).unwrap();
+        assert_eq!(french[0].text, "bonjour");
+        assert_eq!(french[0].start, 1.5);
+
+        // Saving the same language again replaces it
+        save_transcript_track(&conn, history_id, &fetched(Some("fr"), "salut")).unwrap();
+        assert_eq!(load_track_segments(&conn, history_id, Some("fr")).unwrap()[0].text, "salut");
+
+        let tracks = load_track_infos(&conn, history_id).unwrap();
+        let languages: Vec<(&str, bool)> = tracks.iter().map(|t| (t.language_code.as_str(), t.is_primary)).collect();
+        assert_eq!(languages, vec![("en", true), ("fr", false)]);
+        assert!(tracks[1].is_auto_generated);
+        assert_eq!(tracks[1].provider.as_deref(), Some("youtube_captions"));
+    }
+
+    #[test]
+    fn test_primary_transcript_without_a_track() {
+        let conn = open_test_db();
+        let history_id = insert_english_video(&conn);
+
+        assert_eq!(load_track_segments(&conn, history_id, None).unwrap()[0].text, "hello");
+        assert_eq!(load_track_segments(&conn, history_id, Some("EN")).unwrap()[0].text, "hello");
+        assert!(load_track_segments(&conn, history_id, Some("de")).is_err());
+
+        // A provider that reports no language is stored as undetermined
+        assert_eq!(save_transcript_track(&conn, history_id, &fetched(None, "???")).unwrap(), UNKNOWN_LANGUAGE);
+        assert_eq!(load_track_segments(&conn, history_id, Some(UNKNOWN_LANGUAGE)).unwrap()[0].text, "???");
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::Path;
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::commands::library::expand_home;
+use crate::commands::quiz::load_quizzes;
+use crate::commands::vision_notebook::load_transcript_details;
+use crate::manifest::Manifest;
+use crate::vault::{render_note, write_note};
+
+/// Export a processed video as a Markdown note in an Obsidian vault
+///
+/// Writes `<vault_dir>/<name>/<name>.md`, named from the title and video id,
+/// with front-matter, the stored summary, chapters, annotations, quizzes and
+/// the transcript linked to its moments, and copies the frames it embeds into
+/// `attachments/` beside it. `~` in `vault_dir` expands to the home directory.
+/// Exporting the same video again updates the note in place. Returns the
+/// note's path.
+#[command]
+pub async fn export_video_to_vault(
+    db: State<'_, AgentDb>,
+    history_id: i64,
+    vault_dir: String,
+) -> Result<String, String> {
+    let (details, quizzes) = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        (load_transcript_details(&conn, history_id)?, load_quizzes(&conn, Some(history_id))?)
+    };
+
+    let manifest_path = details.manifest_path.as_deref().map(Path::new).filter(|path| path.exists());
+    let manifest = manifest_path.map(Manifest::load).transpose()?;
+    let note = render_note(&details, &quizzes, manifest.as_ref(), manifest_path.and_then(Path::parent));
+
+    let path = write_note(&expand_home(&vault_dir)?, &note)?;
+    Ok(path.to_string_lossy().to_string())
+}
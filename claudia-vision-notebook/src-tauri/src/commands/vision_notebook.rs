@@ -1,12 +1,32 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use tauri::{command, AppHandle, Manager, State};
 use crate::commands::agents::AgentDb;
+use crate::commands::history::{create_notebook_entry, delete_notebook_entries};
+use crate::chapters::chapter_segments;
+use crate::commands::chapters::store_chapters;
+use crate::commands::quiz::delete_quiz_rows;
+use crate::commands::library::project_dir_path;
+use crate::commands::annotations::{delete_annotations, load_annotations, TranscriptAnnotation};
+use crate::commands::search::{fts_query, index_segments, remove_segments};
+use crate::commands::transcript_tracks::{load_track_segments, save_transcript_track};
+use crate::manifest::{Chapter, ContextBudget, Manifest};
+use crate::progress::{parse_ytdlp_progress, DownloadProgress, PipelineStage, ProgressReporter};
+use crate::commands::whisper::WhisperConfigState;
+use crate::media::{extract_audio, extract_frames, probe_media, FrameSampling, SampledFrame};
+use crate::structured_output::extract_json_array;
+use crate::transcript::subtitles::{format_clock, parse_subtitles};
+use crate::transcript::whisper::transcribe_audio;
+use crate::transcript::youtube::{iso8601_duration, timestamp_url};
+use crate::transcript::{FetchedTranscript, TranscriptProviderState};
+use log::warn;
 use rusqlite::params;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+use sha2::{Digest, Sha256};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoInfo {
     pub video_id: String,
     pub title: String,
@@ -15,21 +35,13 @@ pub struct VideoInfo {
     pub thumbnail_url: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptSegment {
     pub text: String,
     pub start: f64,
     pub duration: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ManifestItem {
-    pub t: f64,
-    pub kind: String, // "text" or "image"
-    pub text: Option<String>,
-    pub path: Option<String>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QuizQuestion {
     pub question: String,
@@ -45,6 +57,7 @@ pub struct ProcessVideoResult {
     pub video_info: Option<VideoInfo>,
     pub transcript: Option<Vec<TranscriptSegment>>,
     pub manifest_path: Option<String>,
+    pub provider: Option<String>,
     pub error: Option<String>,
 }
 
@@ -60,6 +73,11 @@ pub struct TranscriptHistoryItem {
     pub transcript_length: i64,
     pub transcript_preview: String,
     pub manifest_path: Option<String>,
+    pub provider: Option<String>,
+    pub source_type: String,
+    pub language_code: Option<String>,
+    /// Transcribed locally from the audio because the video had no captions
+    pub is_machine_transcribed: bool,
     pub fetched_at: String,
 }
 
@@ -74,6 +92,20 @@ pub struct TranscriptHistoryDetails {
     pub thumbnail_url: Option<String>,
     pub transcript: Vec<TranscriptSegment>,
     pub manifest_path: Option<String>,
+    pub provider: Option<String>,
+    pub source_type: String,
+    pub language_code: Option<String>,
+    /// Transcribed locally from the audio because the video had no captions
+    pub is_machine_transcribed: bool,
+    pub chapters: Vec<Chapter>,
+    /// How the chapters were found: "description", "segmentation" or "llm"
+    pub chapter_source: Option<String>,
+    /// `transcript` after caption cleanup, if it has been run
+    pub cleaned_transcript: Option<Vec<TranscriptSegment>>,
+    /// The user's notes, in time order
+    pub annotations: Vec<TranscriptAnnotation>,
+    /// Latest summary of the whole video from `summarize_video`
+    pub summary: Option<String>,
     pub fetched_at: String,
 }
 
@@ -90,155 +122,387 @@ pub async fn extract_video_id(url: String) -> Result<String, String> {
 }
 
 /// Process a YouTube video: download, extract transcript, sample frames
+///
+/// `language` picks the caption track used for the transcript and manifest;
+/// without it the best English track (or any track) is used. Frames are
+/// sampled at `fps`, or once per scene change when `scene_threshold` is set.
+/// The video's folder is created in `project_dir`, or the library root when it
+/// is not given. Runs inline; `enqueue_youtube_video_job` runs the same work
+/// in the background. Each stage is reported through `video-progress` events.
 #[command]
 pub async fn process_youtube_video(
-    db: State<'_, AgentDb>,
+    app: AppHandle,
     url: String,
-    project_dir: String,
+    project_dir: Option<String>,
     fps: Option<f64>,
+    language: Option<String>,
+    scene_threshold: Option<f64>,
 ) -> Result<ProcessVideoResult, String> {
-    let video_id = extract_video_id(url.clone()).await?;
+    let request = YouTubeVideoRequest {
+        url,
+        project_dir,
+        fps,
+        language,
+        scene_threshold,
+    };
+    let db = app.state::<AgentDb>();
+    let providers = app.state::<TranscriptProviderState>();
+    let mut progress = YouTubeVideoProgress::default();
+    run_youtube_pipeline(&app, &db, &providers, &request, &mut progress, &mut |_, _| Ok(())).await
+}
+
+/// Arguments of `process_youtube_video`, as stored with a queued job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YouTubeVideoRequest {
+    pub url: String,
+    /// Folder the video's folder is created in; the library root when absent
+    pub project_dir: Option<String>,
+    pub fps: Option<f64>,
+    pub language: Option<String>,
+    pub scene_threshold: Option<f64>,
+}
+
+/// Stages of a YouTube video that have finished, so a resumed job can skip them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct YouTubeVideoProgress {
+    pub transcript: Option<FetchedTranscript>,
+    pub video_path: Option<PathBuf>,
+    pub frames: Option<Vec<SampledFrame>>,
+}
+
+/// Fetch, download, sample and save one YouTube video
+///
+/// Stages already recorded in `progress` are skipped. `checkpoint` is called
+/// with the stage name ("transcript", "download", "frames", "save") before
+/// each stage runs, with everything finished so far.
+pub(crate) async fn run_youtube_pipeline(
+    app: &AppHandle,
+    db: &AgentDb,
+    providers: &TranscriptProviderState,
+    request: &YouTubeVideoRequest,
+    progress: &mut YouTubeVideoProgress,
+    checkpoint: &mut (dyn FnMut(&str, &YouTubeVideoProgress) -> Result<(), String> + Send),
+) -> Result<ProcessVideoResult, String> {
+    let sampling = frame_sampling(request.fps, request.scene_threshold);
+    let video_id = extract_video_id(request.url.clone()).await?;
+    let mut reporter = ProgressReporter::new(app, &video_id);
     
     // Create project directory for this video
-    let video_dir = PathBuf::from(&project_dir).join(&video_id);
+    let video_dir = project_dir_path(db, request.project_dir.as_deref())?.join(&video_id);
     std::fs::create_dir_all(&video_dir)
         .map_err(|e| format!("Failed to create video directory: {}", e))?;
 
-    // Step 1: Download video info and transcript using existing Node.js service
-    let transcript_result = call_transcript_service(&video_id, &project_dir).await?;
+    // Step 1: Fetch video info and transcript from the first provider that has one
+    if progress.transcript.is_none() {
+        checkpoint("transcript", progress)?;
+        reporter.stage(PipelineStage::FetchingTranscript);
+        let provider_order = {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            load_provider_order(&conn)
+        };
+        progress.transcript = Some(
+            providers.0
+                .fetch(&video_id, request.language.as_deref(), &provider_order)
+                .await?,
+        );
+    }
     
-    // Step 2: Download video (optional, for frame extraction)
-    let video_path = if fps.is_some() {
-        Some(download_video(&video_id, &video_dir).await?)
-    } else {
-        None
-    };
+    // Step 2: Download video (optional, for frame extraction). yt-dlp picks up
+    // a partial download left by an interrupted run.
+    let downloaded = progress.video_path.as_ref().is_some_and(|path| path.exists());
+    if sampling.is_some() && !downloaded {
+        checkpoint("download", progress)?;
+        reporter.stage(PipelineStage::Downloading);
+        let video_path = download_video(&video_id, &video_dir, |update| reporter.download(&update)).await?;
+        progress.video_path = Some(video_path);
+    }
     
     // Step 3: Extract frames if video was downloaded
-    let frame_paths = if let (Some(video_path), Some(fps_val)) = (&video_path, fps) {
-        extract_frames(&video_path, &video_dir, fps_val).await?
-    } else {
-        Vec::new()
-    };
+    if progress.frames.is_none() {
+        if let (Some(video_path), Some(sampling)) = (progress.video_path.clone(), sampling) {
+            checkpoint("frames", progress)?;
+            reporter.stage(PipelineStage::ExtractingFrames);
+            let duration = probe_media(&video_path).await.ok().and_then(|info| info.duration);
+            let frames = extract_frames(&video_path, &video_dir, sampling, duration, |extracted, expected, percent| {
+                reporter.frames(extracted, expected, percent)
+            }).await?;
+            progress.frames = Some(frames);
+        }
+    }
+    
+    checkpoint("save", progress)?;
+    let transcript_result = progress.transcript.clone()
+        .ok_or_else(|| "Transcript stage did not finish".to_string())?;
+    let frames = progress.frames.clone().unwrap_or_default();
     
     // Step 4: Build manifest
-    let manifest = build_manifest(&transcript_result.transcript, &frame_paths).await?;
+    reporter.stage(PipelineStage::WritingManifest);
+    let mut manifest = Manifest::build(
+        &transcript_result.video_info,
+        &transcript_result.segments,
+        &frames,
+        Some(&transcript_result.provider),
+        sampling,
+    );
+    manifest.chapters = transcript_result.chapters.clone();
     let manifest_path = video_dir.join("manifest.json");
-    
-    let manifest_json = serde_json::to_string_pretty(&manifest)
-        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
-    
-    std::fs::write(&manifest_path, manifest_json)
-        .map_err(|e| format!("Failed to save manifest: {}", e))?;
+    manifest.save(&manifest_path)?;
 
-    // Save to transcript history
-    if let Some(ref transcript) = transcript_result.transcript {
-        // Store transcript as JSON to preserve timing information
-        let transcript_json = serde_json::to_string(&transcript)
-            .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
-        
+    // Save to transcript history, which indexes it for search
+    reporter.stage(PipelineStage::Indexing);
+    {
         let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let history_id = save_transcript_history(
+            &conn,
+            &transcript_result.video_info,
+            &request.url,
+            &transcript_result.segments,
+            &manifest_path,
+            Some(&transcript_result.provider),
+            "youtube",
+        )?;
         
-        // Use INSERT OR REPLACE to update existing entries
+        // Keep the track alongside any other languages fetched for this video
+        let language_code = save_transcript_track(&conn, history_id, &transcript_result)?;
         conn.execute(
-            "INSERT OR REPLACE INTO transcript_history 
-            (video_id, video_url, title, channel, duration, thumbnail_url, 
-             transcript_length, transcript_text, manifest_path)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![
-                &video_id,
-                &url,
-                &transcript_result.video_info.title,
-                &transcript_result.video_info.channel,
-                &transcript_result.video_info.duration,
-                &transcript_result.video_info.thumbnail_url,
-                transcript.len() as i64,
-                &transcript_json,
-                manifest_path.to_string_lossy().to_string()
-            ],
+            "UPDATE transcript_history SET language_code = ?1, is_machine_transcribed = ?2 WHERE id = ?3",
+            params![&language_code, transcript_result.is_machine_transcribed, history_id],
         ).map_err(|e| format!("Failed to save transcript history: {}", e))?;
+        
+        if !transcript_result.chapters.is_empty() {
+            store_chapters(&conn, history_id, &transcript_result.chapters, "description")?;
+        }
     }
+    reporter.stage(PipelineStage::Done);
 
     Ok(ProcessVideoResult {
         success: true,
         video_info: Some(transcript_result.video_info),
-        transcript: transcript_result.transcript,
+        transcript: Some(transcript_result.segments),
         manifest_path: Some(manifest_path.to_string_lossy().to_string()),
+        provider: Some(transcript_result.provider),
+        error: None,
+    })
+}
+
+/// Process a video file on disk: probe it, sample frames, pair sidecar subtitles
+///
+/// Without sidecar subtitles the audio is transcribed locally when a whisper
+/// model is configured. Each stage is reported through `video-progress` events.
+#[command]
+pub async fn process_local_video(
+    app: AppHandle,
+    db: State<'_, AgentDb>,
+    file_path: String,
+    project_dir: Option<String>,
+    fps: Option<f64>,
+    title: Option<String>,
+    scene_threshold: Option<f64>,
+) -> Result<ProcessVideoResult, String> {
+    let video_path = PathBuf::from(&file_path)
+        .canonicalize()
+        .map_err(|e| format!("Video file not found: {}", e))?;
+    let video_id = local_video_id(&video_path.to_string_lossy());
+    let mut reporter = ProgressReporter::new(&app, &video_id);
+    
+    let video_dir = project_dir_path(&db, project_dir.as_deref())?.join(&video_id);
+    std::fs::create_dir_all(&video_dir)
+        .map_err(|e| format!("Failed to create video directory: {}", e))?;
+    
+    // Step 1: Read duration and container metadata
+    let media_info = probe_media(&video_path).await?;
+    
+    // Step 2: Use sidecar subtitles (talk.srt, talk.en.vtt, ...) as the transcript
+    let (transcript, provider) = match find_sidecar_subtitles(&video_path) {
+        Some(subtitle_path) => {
+            let content = std::fs::read_to_string(&subtitle_path)
+                .map_err(|e| format!("Failed to read subtitle file: {}", e))?;
+            (parse_subtitles(&content)?, Some("subtitle_file"))
+        }
+        None => {
+            let whisper = app.state::<WhisperConfigState>().0.read().map_err(|e| e.to_string())?.clone();
+            if media_info.has_audio && whisper.model().is_ok() {
+                reporter.stage(PipelineStage::Transcribing);
+                let workdir = tempfile::tempdir()
+                    .map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+                let audio_path = workdir.path().join("audio.wav");
+                extract_audio(&video_path, &audio_path).await?;
+                (transcribe_audio(&whisper, &audio_path, None).await?.segments, Some("whisper"))
+            } else {
+                (Vec::new(), None)
+            }
+        }
+    };
+    
+    // Step 3: Extract frames straight from the file
+    let sampling = frame_sampling(fps, scene_threshold);
+    let frames = match sampling {
+        Some(sampling) => {
+            reporter.stage(PipelineStage::ExtractingFrames);
+            extract_frames(&video_path, &video_dir, sampling, media_info.duration, |extracted, expected, percent| {
+                reporter.frames(extracted, expected, percent)
+            }).await?
+        }
+        None => Vec::new(),
+    };
+    
+    let video_info = VideoInfo {
+        video_id: video_id.clone(),
+        title: title
+            .or(media_info.title)
+            .or_else(|| video_path.file_stem().map(|s| s.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "Local video".to_string()),
+        channel: media_info.artist.unwrap_or_else(|| "Local video".to_string()),
+        duration: media_info.duration.map(|d| iso8601_duration(d.round() as u64)),
+        thumbnail_url: frames.first().map(|f| f.path.to_string_lossy().to_string()),
+    };
+    
+    // Step 4: Build manifest
+    reporter.stage(PipelineStage::WritingManifest);
+    let manifest = Manifest::build(&video_info, &transcript, &frames, provider, sampling);
+    let manifest_path = video_dir.join("manifest.json");
+    manifest.save(&manifest_path)?;
+    
+    // Save to transcript history, which indexes it in the notebook
+    reporter.stage(PipelineStage::Indexing);
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let history_id = save_transcript_history(
+            &conn,
+            &video_info,
+            &format!("file://{}", video_path.display()),
+            &transcript,
+            &manifest_path,
+            provider,
+            "local_video",
+        )?;
+        conn.execute(
+            "UPDATE transcript_history SET is_machine_transcribed = ?1 WHERE id = ?2",
+            params![provider == Some("whisper"), history_id],
+        ).map_err(|e| format!("Failed to save transcript history: {}", e))?;
+    }
+    reporter.stage(PipelineStage::Done);
+    
+    Ok(ProcessVideoResult {
+        success: true,
+        video_info: Some(video_info),
+        transcript: Some(transcript),
+        manifest_path: Some(manifest_path.to_string_lossy().to_string()),
+        provider: provider.map(|p| p.to_string()),
         error: None,
     })
 }
 
 /// Generate quiz questions from transcript using Claude CLI
+///
+/// Questions are spread evenly over the transcript's chunks, and with
+/// `chapters` each chunk is drawn from a single chapter. Chunks left without
+/// a question are not sent.
 #[command]
 pub async fn generate_quiz_from_transcript(
     transcript: Vec<TranscriptSegment>,
     num_questions: Option<usize>,
     difficulty: Option<String>,
+    chapters: Option<Vec<Chapter>>,
 ) -> Result<Vec<QuizQuestion>, String> {
     let num_q = num_questions.unwrap_or(5);
     let diff = difficulty.unwrap_or_else(|| "medium".to_string());
+    let chapters = chapters.unwrap_or_default();
     
     // Combine transcript into text chunks (respecting token limits)
-    let text_chunks = chunk_transcript(&transcript, 40000)?; // ~50k tokens max
+    let text_chunks = chapter_chunks(&transcript, &chapters, 40000)?; // ~50k tokens max
+    let quotas = question_quotas(num_q, text_chunks.len());
     
     let mut all_questions = Vec::new();
     
-    for chunk in text_chunks {
+    for (chunk, per_chunk) in text_chunks.into_iter().zip(quotas) {
+        if per_chunk == 0 {
+            continue;
+        }
+        let excerpt = match &chunk.chapter {
+            Some(title) => format!("the chapter \"{}\", {} to {}", title, format_clock(chunk.start_time), format_clock(chunk.end_time)),
+            None => format!("{} to {}", format_clock(chunk.start_time), format_clock(chunk.end_time)),
+        };
         let prompt = format!(
-            "Based on the following transcript excerpt, generate {} {} difficulty multiple choice questions. 
-            For each question provide: question text, 4 answer options, correct answer index (0-3), 
-            and a brief explanation. Format as JSON array.
+            "Based on the following transcript excerpt ({}), generate {} {} difficulty multiple choice questions. 
+            Respond with only a JSON array of objects with these fields:
+            \"question\": the question text,
+            \"options\": exactly 4 answer strings,
+            \"correct_answer\": index of the correct option (0-3),
+            \"explanation\": a brief explanation,
+            \"timestamp\": seconds from the start of the video where the answer is discussed.
             
             Transcript:
             {}",
-            num_q, diff, chunk.text
+            excerpt, per_chunk, diff, chunk.text
         );
         
-        // Call Claude CLI
-        let claude_output = call_claude_cli(&prompt, None).await?;
-        
-        // Parse JSON response
-        let questions: Vec<QuizQuestion> = serde_json::from_str(&claude_output)
-            .map_err(|e| format!("Failed to parse Claude response: {}", e))?;
+        // Call Claude CLI, feeding validation errors back until the output is usable
+        let mut attempt_prompt = prompt.clone();
+        let mut attempt = 1;
+        let mut questions = loop {
+            let claude_output = call_claude_cli(&attempt_prompt, None).await?;
+            
+            match parse_quiz_questions(&claude_output, &chunk) {
+                Ok(questions) => break questions,
+                Err(e) if attempt < MAX_QUIZ_ATTEMPTS => {
+                    warn!("Quiz response rejected (attempt {}): {}", attempt, e);
+                    attempt += 1;
+                    attempt_prompt = format!(
+                        "{}\n\nYour previous response was rejected: {}\nPrevious response:\n{}\n\nRespond with only the corrected JSON array.",
+                        prompt, e, claude_output.chars().take(4000).collect::<String>()
+                    );
+                }
+                Err(e) => {
+                    return Err(format!("Failed to parse Claude response after {} attempts: {}", attempt, e))
+                }
+            }
+        };
         
+        questions.truncate(per_chunk);
         all_questions.extend(questions);
     }
     
-    // Limit to requested number of questions
-    all_questions.truncate(num_q);
     Ok(all_questions)
 }
 
 /// Ask Claude a question about the video using transcript and frames
+///
+/// With a `timestamp`, only text and frames within `context_window` seconds of
+/// it are sent. Frames are capped at `max_frames` and text at `max_context_tokens`.
 #[command]
 pub async fn ask_video_question(
     question: String,
     manifest_path: String,
     context_window: Option<f64>, // seconds around current timestamp
+    timestamp: Option<f64>,
+    max_frames: Option<usize>,
+    max_context_tokens: Option<usize>,
 ) -> Result<String, String> {
     // Load manifest
-    let manifest_content = std::fs::read_to_string(&manifest_path)
-        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let manifest = Manifest::load(Path::new(&manifest_path))?;
     
-    let manifest: Vec<ManifestItem> = serde_json::from_str(&manifest_content)
-        .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    // Build context from manifest (transcript + frames near the timestamp)
+    let budget = ContextBudget {
+        max_chars: max_context_tokens.unwrap_or(DEFAULT_CONTEXT_TOKENS) * CHARS_PER_TOKEN,
+        max_frames: max_frames.unwrap_or(DEFAULT_MAX_FRAMES),
+    };
+    let context = manifest.select_context(timestamp, context_window.unwrap_or(30.0), budget);
     
-    // Build context from manifest (transcript + relevant frames)
-    let context = build_context_from_manifest(&manifest, context_window.unwrap_or(30.0))?;
+    let position = timestamp
+        .map(|t| format!("The viewer is at {} in the video.\n", format_clock(t)))
+        .unwrap_or_default();
     
     let prompt = format!(
-        "Based on the following video content (transcript and frame descriptions), answer this question: {}
-        
+        "Based on the following video content (timestamped transcript and frames), answer this question: {}
+        {}
         Video Content:
         {}",
-        question, context
+        question, position, context.text
     );
     
     // Call Claude CLI with multimodal support if frames are present
-    let image_paths: Vec<String> = manifest.iter()
-        .filter(|item| item.kind == "image")
-        .filter_map(|item| item.path.clone())
-        .collect();
+    let image_paths = context.image_paths;
     
     let response = call_claude_cli(&prompt, if image_paths.is_empty() { None } else { Some(image_paths) }).await?;
     
@@ -246,183 +510,480 @@ pub async fn ask_video_question(
 }
 
 /// Summarize video content using Claude CLI
+///
+/// Long transcripts are summarized chunk by chunk, each with its time range,
+/// and the partial summaries are combined into sections anchored by timestamp.
+/// When the manifest has chapters, chunks follow them and the summary is
+/// organized by chapter; `chapter` summarizes just that one (by index).
+/// `language` summarizes a stored transcript track instead of the manifest text.
+/// Summaries of the whole video are kept for `export_video_to_vault`.
 #[command]
 pub async fn summarize_video(
+    db: State<'_, AgentDb>,
     manifest_path: String,
     summary_type: Option<String>, // "brief", "detailed", "bullet_points"
+    language: Option<String>,
+    chapter: Option<usize>,
 ) -> Result<String, String> {
     let summary_style = summary_type.unwrap_or_else(|| "detailed".to_string());
+    let manifest = Manifest::load(Path::new(&manifest_path))?;
     
-    // Load manifest
-    let manifest_content = std::fs::read_to_string(&manifest_path)
-        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let transcript = if let Some(language) = language {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        let history_id: i64 = conn.query_row(
+            "SELECT id FROM transcript_history WHERE manifest_path = ?1",
+            params![&manifest_path],
+            |row| row.get(0),
+        ).map_err(|_| "No transcript history for this manifest".to_string())?;
+        
+        load_track_segments(&conn, history_id, Some(&language))?
+    } else {
+        // Extract transcript from the manifest
+        manifest.transcript()
+    };
     
-    let manifest: Vec<ManifestItem> = serde_json::from_str(&manifest_content)
-        .map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    let (transcript, chapters) = match chapter {
+        Some(index) => {
+            let chapter = manifest.chapters.get(index)
+                .ok_or_else(|| format!("The video has no chapter {}", index))?;
+            (chapter_segments(&transcript, chapter).to_vec(), Vec::new())
+        }
+        None => (transcript, manifest.chapters.clone()),
+    };
     
-    // Extract transcript text
-    let transcript_text: String = manifest.iter()
-        .filter(|item| item.kind == "text")
-        .filter_map(|item| item.text.as_ref())
-        .cloned()
-        .collect::<Vec<String>>()
-        .join(" ");
+    let chunks = chapter_chunks(&transcript, &chapters, SUMMARY_CHUNK_CHARS)?;
+    
+    if chunks.len() <= 1 {
+        let text = chunks.first().map(|c| c.text.as_str()).unwrap_or("");
+        let prompt = format!(
+            "Please provide a {} summary of the following video transcript. 
+            Organize it into sections and start each section with the [MM:SS] timestamp where it begins.
+            
+            {}",
+            summary_style, text
+        );
+        let summary = call_claude_cli(&prompt, None).await?;
+        if chapter.is_none() {
+            save_summary(&db, &manifest_path, &summary)?;
+        }
+        return Ok(summary);
+    }
+    
+    // Map: summarize each chunk on its own
+    let mut partials = Vec::new();
+    for chunk in &chunks {
+        let chapter = chunk.chapter.as_deref()
+            .map(|title| format!(" in the chapter \"{}\"", title))
+            .unwrap_or_default();
+        let prompt = format!(
+            "Summarize this part of a video transcript, covering {} to {}{}. 
+            Write concise bullet points and start each with the [MM:SS] timestamp of the point it describes.
+            
+            {}",
+            format_clock(chunk.start_time), format_clock(chunk.end_time), chapter, chunk.text
+        );
+        partials.push(TranscriptChunk {
+            text: call_claude_cli(&prompt, None).await?,
+            start_time: chunk.start_time,
+            end_time: chunk.end_time,
+            chapter: chunk.chapter.clone(),
+        });
+    }
+    
+    // Reduce: merge neighbouring summaries until they fit in one prompt
+    while partials.len() > 1 && section_text(&partials).len() > SUMMARY_CHUNK_CHARS {
+        let count = partials.len();
+        let batches = batch_summaries(partials, SUMMARY_CHUNK_CHARS);
+        if batches.len() == count {
+            // Every summary is already too long to pair up; cut each to its share
+            partials = batches.into_iter().flatten().collect();
+            shorten_summaries(&mut partials, SUMMARY_CHUNK_CHARS);
+            break;
+        }
+        let mut merged = Vec::new();
+        for mut batch in batches {
+            if batch.len() == 1 {
+                // Nothing to combine it with; keep it as it is
+                merged.extend(batch.pop());
+                continue;
+            }
+            let prompt = format!(
+                "Combine these consecutive section summaries of a video into one set of bullet points. 
+                Keep the [MM:SS] timestamps of the points you keep.
+                
+                {}",
+                section_text(&batch)
+            );
+            merged.push(TranscriptChunk {
+                text: call_claude_cli(&prompt, None).await?,
+                start_time: batch.first().map(|c| c.start_time).unwrap_or(0.0),
+                end_time: batch.last().map(|c| c.end_time).unwrap_or(0.0),
+                chapter: None,
+            });
+        }
+        partials = merged;
+    }
     
+    let organization = if chapters.is_empty() {
+        "Organize it into sections, head each section with the [MM:SS] timestamp where it begins".to_string()
+    } else {
+        let outline: Vec<String> = chapters.iter()
+            .map(|c| format!("[{}] {}", format_clock(c.start), c.title))
+            .collect();
+        format!("Organize it by the video's chapters, each headed by its [MM:SS] start and title ({})", outline.join("; "))
+    };
     let prompt = format!(
-        "Please provide a {} summary of the following video transcript:
+        "Below are summaries of consecutive sections of one video, each headed by its time range. 
+        Combine them into a single {} summary. {}, and keep the timestamps of key points.
         
         {}",
-        summary_style, transcript_text
+        summary_style, organization, section_text(&partials)
     );
     
-    call_claude_cli(&prompt, None).await
+    let summary = call_claude_cli(&prompt, None).await?;
+    if chapter.is_none() {
+        save_summary(&db, &manifest_path, &summary)?;
+    }
+    Ok(summary)
 }
 
-// Helper functions
-
-#[derive(Deserialize)]
-struct TranscriptServiceResult {
-    video_info: VideoInfo,
-    transcript: Option<Vec<TranscriptSegment>>,
+/// Get transcript providers in the order they are tried
+#[command]
+pub async fn get_transcript_providers(
+    db: State<'_, AgentDb>,
+    providers: State<'_, TranscriptProviderState>,
+) -> Result<Vec<String>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let order = load_provider_order(&conn);
+    
+    Ok(providers.0.ordered(&order).iter().map(|p| p.name().to_string()).collect())
 }
 
-async fn call_transcript_service(video_id: &str, _project_dir: &str) -> Result<TranscriptServiceResult, String> {
-    let bridge_script = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?
-        .join("scripts")
-        .join("clean-bridge.js");
-    
-    let output = Command::new("node")
-        .arg(&bridge_script)
-        .arg("fetch")
-        .arg(video_id)
-        .output()
-        .map_err(|e| format!("Failed to execute transcript bridge: {}", e))?;
-    
-    if !output.status.success() {
-        return Err(format!("Transcript service failed: {}", String::from_utf8_lossy(&output.stderr)));
+/// Set the order in which transcript providers are tried
+#[command]
+pub async fn set_transcript_provider_order(
+    db: State<'_, AgentDb>,
+    providers: State<'_, TranscriptProviderState>,
+    order: Vec<String>,
+) -> Result<(), String> {
+    let known = providers.0.names();
+    if let Some(unknown) = order.iter().find(|name| !known.contains(name)) {
+        return Err(format!("Unknown transcript provider: {}", unknown));
     }
     
-    let result_str = String::from_utf8_lossy(&output.stdout);
-    let result: serde_json::Value = serde_json::from_str(&result_str)
-        .map_err(|e| format!("Failed to parse transcript service response: {}", e))?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2",
+        params![PROVIDER_ORDER_SETTING, order.join(",")],
+    ).map_err(|e| format!("Failed to save provider order: {}", e))?;
     
-    if !result["success"].as_bool().unwrap_or(false) {
-        return Err(result["error"].as_str().unwrap_or("Unknown error").to_string());
+    Ok(())
+}
+
+// Helper functions
+
+const PROVIDER_ORDER_SETTING: &str = "transcript_provider_order";
+
+/// Model calls per chunk before quiz generation gives up
+const MAX_QUIZ_ATTEMPTS: usize = 3;
+
+/// Largest transcript or summary text sent in one summarization prompt
+const SUMMARY_CHUNK_CHARS: usize = 40000;
+
+/// Rough conversion used to budget prompt text
+const CHARS_PER_TOKEN: usize = 4;
+const DEFAULT_CONTEXT_TOKENS: usize = 8_000;
+const DEFAULT_MAX_FRAMES: usize = 4;
+
+/// Read the configured transcript provider order from app settings
+pub(crate) fn load_provider_order(conn: &rusqlite::Connection) -> Vec<String> {
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        params![PROVIDER_ORDER_SETTING],
+        |row| row.get::<_, String>(0),
+    )
+    .map(|value| {
+        value
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Scene detection takes precedence over a fixed frame rate
+fn frame_sampling(fps: Option<f64>, scene_threshold: Option<f64>) -> Option<FrameSampling> {
+    match (scene_threshold, fps) {
+        (Some(threshold), _) => Some(FrameSampling::SceneChange { threshold }),
+        (None, Some(fps)) => Some(FrameSampling::Fps { fps }),
+        (None, None) => None,
     }
-    
-    // Convert to our format
-    let transcript = if let Some(transcript_array) = result["transcript"].as_array() {
-        Some(transcript_array.iter()
-            .map(|item| TranscriptSegment {
-                text: item["text"].as_str().unwrap_or("").to_string(),
-                start: item["start"].as_f64().unwrap_or(0.0),
-                duration: item["duration"].as_f64().unwrap_or(4.0),
-            })
-            .collect())
+}
+
+/// Link that opens a history entry's source at `seconds`
+///
+/// YouTube videos get a `youtu.be` link; local files use a media fragment.
+pub(crate) fn source_link(source_type: &str, video_id: &str, video_url: &str, seconds: f64) -> String {
+    if source_type == "youtube" {
+        timestamp_url(video_id, seconds)
     } else {
-        None
-    };
-    
-    let video_info_json = &result["video_info"];
-    
-    Ok(TranscriptServiceResult {
-        video_info: VideoInfo {
-            video_id: video_info_json["video_id"].as_str().unwrap_or(video_id).to_string(),
-            title: video_info_json["title"].as_str().unwrap_or("Unknown Title").to_string(),
-            channel: video_info_json["channel"].as_str().unwrap_or("Unknown Channel").to_string(),
-            duration: video_info_json["duration"].as_str().map(|s| s.to_string()),
-            thumbnail_url: Some(format!("https://img.youtube.com/vi/{}/maxresdefault.jpg", video_id)),
-        },
-        transcript,
-    })
+        format!("{}#t={}", video_url, seconds.max(0.0).floor() as u64)
+    }
 }
 
-async fn download_video(video_id: &str, video_dir: &PathBuf) -> Result<PathBuf, String> {
-    let output_path = video_dir.join(format!("{}.mp4", video_id));
-    
-    let output = Command::new("yt-dlp")
-        .arg("-f")
-        .arg("best[height<=720]") // Limit quality for performance
-        .arg("-o")
-        .arg(&output_path)
-        .arg(&format!("https://www.youtube.com/watch?v={}", video_id))
-        .output()
-        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+/// Synthetic video id for material that did not come from YouTube
+pub(crate) fn local_video_id(seed: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+    format!("local-{}", &hash[..12])
+}
+
+/// Insert or update the transcript history row for a video and return its id
+///
+/// The row keeps its id so stored tracks stay attached. Its notebook entry is
+/// replaced, so re-processing a video does not leave stale search results
+/// behind and every source ends up in the notebook.
+pub(crate) fn save_transcript_history(
+    conn: &rusqlite::Connection,
+    video_info: &VideoInfo,
+    video_url: &str,
+    segments: &[TranscriptSegment],
+    manifest_path: &Path,
+    provider: Option<&str>,
+    source_type: &str,
+) -> Result<i64, String> {
+    // Store transcript as JSON to preserve timing information
+    let transcript_json = serde_json::to_string(segments)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
     
-    if !output.status.success() {
-        return Err(format!("yt-dlp failed: {}", String::from_utf8_lossy(&output.stderr)));
+    if let Ok(previous_id) = conn.query_row(
+        "SELECT id FROM transcript_history WHERE video_id = ?1",
+        params![&video_info.video_id],
+        |row| row.get::<_, i64>(0),
+    ) {
+        delete_notebook_entries(conn, "transcript", previous_id)
+            .map_err(|e| format!("Failed to replace notebook entry: {}", e))?;
     }
     
-    Ok(output_path)
+    conn.execute(
+        "INSERT INTO transcript_history 
+        (video_id, video_url, title, channel, duration, thumbnail_url, 
+         transcript_length, transcript_text, manifest_path, provider, source_type)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ON CONFLICT(video_id) DO UPDATE SET
+            video_url = excluded.video_url,
+            title = excluded.title,
+            channel = excluded.channel,
+            duration = excluded.duration,
+            thumbnail_url = excluded.thumbnail_url,
+            transcript_length = excluded.transcript_length,
+            transcript_text = excluded.transcript_text,
+            manifest_path = excluded.manifest_path,
+            provider = excluded.provider,
+            source_type = excluded.source_type,
+            language_code = NULL,
+            is_machine_transcribed = 0,
+            cleaned_transcript = NULL,
+            summary = NULL,
+            chapters = NULL,
+            chapter_source = NULL,
+            fetched_at = CURRENT_TIMESTAMP",
+        params![
+            &video_info.video_id,
+            video_url,
+            &video_info.title,
+            &video_info.channel,
+            &video_info.duration,
+            &video_info.thumbnail_url,
+            segments.len() as i64,
+            &transcript_json,
+            manifest_path.to_string_lossy().to_string(),
+            provider,
+            source_type
+        ],
+    ).map_err(|e| format!("Failed to save transcript history: {}", e))?;
+    
+    let history_id = conn.query_row(
+        "SELECT id FROM transcript_history WHERE video_id = ?1",
+        params![&video_info.video_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Failed to save transcript history: {}", e))?;
+    
+    index_segments(conn, history_id, segments)?;
+    index_transcript_entry(conn, history_id, video_info, segments)?;
+    Ok(history_id)
+}
+
+/// Remember the summary of the video a manifest belongs to
+fn save_summary(db: &AgentDb, manifest_path: &str, summary: &str) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE transcript_history SET summary = ?1 WHERE manifest_path = ?2",
+        params![summary, manifest_path],
+    ).map_err(|e| format!("Failed to save summary: {}", e))?;
+    Ok(())
 }
 
-async fn extract_frames(video_path: &PathBuf, output_dir: &PathBuf, fps: f64) -> Result<Vec<PathBuf>, String> {
-    let frames_dir = output_dir.join("frames");
-    std::fs::create_dir_all(&frames_dir)
-        .map_err(|e| format!("Failed to create frames directory: {}", e))?;
+/// Delete a history entry with everything stored for it, including its notebook entry
+///
+/// Foreign keys are not enforced, so the rows that refer to the entry are
+/// deleted here, all in one transaction.
+pub(crate) fn delete_history_rows(conn: &rusqlite::Connection, history_id: i64) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let quiz_ids: Vec<i64> = {
+        let mut stmt = tx.prepare("SELECT id FROM quizzes WHERE history_id = ?")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt.query_map(params![history_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<i64>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+    for quiz_id in quiz_ids {
+        delete_quiz_rows(&tx, quiz_id)?;
+    }
     
-    let output_pattern = frames_dir.join("frame_%06d.jpg");
+    remove_segments(&tx, history_id)?;
+    delete_annotations(&tx, history_id)?;
     
-    let output = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(video_path)
-        .arg("-vf")
-        .arg(&format!("fps={}", fps))
-        .arg("-y")
-        .arg(&output_pattern)
-        .output()
-        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    tx.execute(
+        "DELETE FROM review_cards WHERE history_id = ?",
+        params![history_id],
+    ).map_err(|e| format!("Failed to delete review cards: {}", e))?;
     
-    if !output.status.success() {
-        return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr)));
+    tx.execute(
+        "DELETE FROM clip_bookmarks WHERE history_id = ?",
+        params![history_id],
+    ).map_err(|e| format!("Failed to delete clips: {}", e))?;
+    
+    tx.execute(
+        "DELETE FROM transcript_tracks WHERE history_id = ?",
+        params![history_id],
+    ).map_err(|e| format!("Failed to delete transcript tracks: {}", e))?;
+    
+    delete_notebook_entries(&tx, "transcript", history_id)
+        .map_err(|e| format!("Failed to delete notebook entry: {}", e))?;
+    
+    tx.execute(
+        "DELETE FROM transcript_history WHERE id = ?",
+        params![history_id],
+    ).map_err(|e| format!("Failed to delete transcript history: {}", e))?;
+    
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Fail unless a history entry exists; rows referring to it are not checked by the database
+pub(crate) fn ensure_history_exists(conn: &rusqlite::Connection, history_id: i64) -> Result<(), String> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM transcript_history WHERE id = ?1)",
+        params![history_id],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    if exists {
+        Ok(())
+    } else {
+        Err(format!("Transcript history {} not found", history_id))
     }
+}
+
+/// Add a transcript to the notebook and its search index
+pub(crate) fn index_transcript_entry(
+    conn: &rusqlite::Connection,
+    history_id: i64,
+    video_info: &VideoInfo,
+    segments: &[TranscriptSegment],
+) -> Result<(), String> {
+    let preview: String = segments.iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(200)
+        .collect();
     
-    // List generated frame files
-    let mut frame_paths = Vec::new();
-    for entry in std::fs::read_dir(&frames_dir)
-        .map_err(|e| format!("Failed to read frames directory: {}", e))?
-    {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        if entry.path().extension().and_then(|s| s.to_str()) == Some("jpg") {
-            frame_paths.push(entry.path());
+    create_notebook_entry(
+        conn,
+        "transcript",
+        Some(history_id),
+        &video_info.title,
+        Some(&preview),
+        Some(&video_info.channel),
+    ).map_err(|e| format!("Failed to create notebook entry: {}", e))
+}
+
+/// Find subtitles next to a video: `talk.srt`, `talk.vtt`, then `talk.<lang>.srt` and friends
+fn find_sidecar_subtitles(video_path: &Path) -> Option<PathBuf> {
+    let stem = video_path.file_stem()?.to_str()?;
+    let dir = video_path.parent()?;
+    
+    for ext in ["srt", "vtt"] {
+        let candidate = dir.join(format!("{}.{}", stem, ext));
+        if candidate.is_file() {
+            return Some(candidate);
         }
     }
     
-    frame_paths.sort();
-    Ok(frame_paths)
+    let prefix = format!("{}.", stem);
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(dir).ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            name.starts_with(&prefix) && (ext.eq_ignore_ascii_case("srt") || ext.eq_ignore_ascii_case("vtt"))
+        })
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
 }
 
-async fn build_manifest(transcript: &Option<Vec<TranscriptSegment>>, frame_paths: &[PathBuf]) -> Result<Vec<ManifestItem>, String> {
-    let mut manifest = Vec::new();
+pub(crate) async fn download_video(
+    video_id: &str,
+    video_dir: &PathBuf,
+    mut on_progress: impl FnMut(DownloadProgress) + Send,
+) -> Result<PathBuf, String> {
+    let output_path = video_dir.join(format!("{}.mp4", video_id));
     
-    // Add transcript items
-    if let Some(transcript_segments) = transcript {
-        for segment in transcript_segments {
-            manifest.push(ManifestItem {
-                t: segment.start,
-                kind: "text".to_string(),
-                text: Some(segment.text.clone()),
-                path: None,
-            });
+    let mut child = tokio::process::Command::new("yt-dlp")
+        .arg("-f")
+        .arg("best[height<=720]") // Limit quality for performance
+        .arg("--newline") // One progress line per update
+        .arg("-o")
+        .arg(&output_path)
+        .arg(&format!("https://www.youtube.com/watch?v={}", video_id))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    
+    let stdout = child.stdout.take().ok_or("Failed to read yt-dlp output")?;
+    let mut stderr = child.stderr.take().ok_or("Failed to read yt-dlp output")?;
+    
+    let read_progress = async {
+        let mut lines = tokio::io::BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(update) = parse_ytdlp_progress(&line) {
+                on_progress(update);
+            }
         }
-    }
+    };
+    let read_errors = async {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors).await;
+        errors
+    };
+    let ((), errors) = tokio::join!(read_progress, read_errors);
     
-    // Add frame items (estimate timestamps based on fps)
-    for (i, frame_path) in frame_paths.iter().enumerate() {
-        let timestamp = i as f64; // TODO: Calculate actual timestamp based on fps
-        manifest.push(ManifestItem {
-            t: timestamp,
-            kind: "image".to_string(),
-            text: None,
-            path: Some(frame_path.to_string_lossy().to_string()),
-        });
+    let status = child.wait().await
+        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+    if !status.success() {
+        return Err(format!("yt-dlp failed: {}", errors));
     }
     
-    // Sort by timestamp
-    manifest.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
-    
-    Ok(manifest)
+    Ok(output_path)
 }
 
 #[derive(Debug)]
@@ -430,6 +991,8 @@ struct TranscriptChunk {
     text: String,
     start_time: f64,
     end_time: f64,
+    /// Chapter the chunk belongs to
+    chapter: Option<String>,
 }
 
 fn chunk_transcript(transcript: &[TranscriptSegment], max_chars: usize) -> Result<Vec<TranscriptChunk>, String> {
@@ -447,6 +1010,7 @@ fn chunk_transcript(transcript: &[TranscriptSegment], max_chars: usize) -> Resul
                 text: current_chunk.clone(),
                 start_time: chunk_start,
                 end_time: last_time,
+                chapter: None,
             });
             current_chunk.clear();
             chunk_start = segment.start;
@@ -465,26 +1029,147 @@ fn chunk_transcript(transcript: &[TranscriptSegment], max_chars: usize) -> Resul
             text: current_chunk,
             start_time: chunk_start,
             end_time: last_time,
+            chapter: None,
         });
     }
     
     Ok(chunks)
 }
 
-fn build_context_from_manifest(manifest: &[ManifestItem], _window: f64) -> Result<String, String> {
-    // For now, just return all text content
-    // TODO: Implement proper windowing and frame integration
-    let text_content: String = manifest.iter()
-        .filter(|item| item.kind == "text")
-        .filter_map(|item| item.text.as_ref())
-        .cloned()
+/// Chunk each chapter on its own so no chunk straddles a chapter boundary
+fn chapter_chunks(
+    transcript: &[TranscriptSegment],
+    chapters: &[Chapter],
+    max_chars: usize,
+) -> Result<Vec<TranscriptChunk>, String> {
+    if chapters.is_empty() {
+        return chunk_transcript(&anchor_segments(transcript), max_chars);
+    }
+    
+    let mut chunks = Vec::new();
+    for chapter in chapters {
+        for mut chunk in chunk_transcript(&anchor_segments(chapter_segments(transcript, chapter)), max_chars)? {
+            chunk.chapter = Some(chapter.title.clone());
+            chunks.push(chunk);
+        }
+    }
+    Ok(chunks)
+}
+
+/// How many of `total` questions each of `chunks` chunks asks, earlier chunks taking the remainder
+fn question_quotas(total: usize, chunks: usize) -> Vec<usize> {
+    (0..chunks)
+        .map(|i| total / chunks + usize::from(i < total % chunks))
+        .collect()
+}
+
+/// Prefix each segment with its time so the model can cite it
+fn anchor_segments(transcript: &[TranscriptSegment]) -> Vec<TranscriptSegment> {
+    transcript.iter()
+        .map(|segment| TranscriptSegment {
+            text: format!("[{}] {}", format_clock(segment.start), segment.text),
+            ..segment.clone()
+        })
+        .collect()
+}
+
+/// Extract quiz questions from model output and check them against the chunk
+fn parse_quiz_questions(output: &str, chunk: &TranscriptChunk) -> Result<Vec<QuizQuestion>, String> {
+    let json = extract_json_array(output)?;
+    let questions: Vec<QuizQuestion> = serde_json::from_str(json)
+        .map_err(|e| format!("Invalid question JSON: {}", e))?;
+    
+    if questions.is_empty() {
+        return Err("The array contains no questions".to_string());
+    }
+    
+    // Timestamps are cited at whole-second resolution
+    let range = chunk.start_time.floor()..=chunk.end_time.ceil();
+    for (i, question) in questions.iter().enumerate() {
+        let n = i + 1;
+        if question.question.trim().is_empty() {
+            return Err(format!("Question {} has no text", n));
+        }
+        if question.options.len() != 4 {
+            return Err(format!("Question {} has {} options; exactly 4 are required", n, question.options.len()));
+        }
+        if question.correct_answer >= question.options.len() {
+            return Err(format!("Question {} has correct_answer {}; it must be between 0 and 3", n, question.correct_answer));
+        }
+        match question.timestamp {
+            Some(t) if range.contains(&t) => {}
+            Some(t) => {
+                return Err(format!(
+                    "Question {} has timestamp {}; it must be between {} and {} seconds",
+                    n, t, range.start(), range.end()
+                ))
+            }
+            None => return Err(format!("Question {} has no timestamp", n)),
+        }
+    }
+    
+    Ok(questions)
+}
+
+/// Section summaries headed by their time range and chapter
+fn section_text(sections: &[TranscriptChunk]) -> String {
+    sections.iter()
+        .map(|s| {
+            let chapter = s.chapter.as_deref().map(|title| format!(" {}", title)).unwrap_or_default();
+            format!("[{} - {}]{}\n{}", format_clock(s.start_time), format_clock(s.end_time), chapter, s.text.trim())
+        })
         .collect::<Vec<String>>()
-        .join(" ");
+        .join("\n\n")
+}
+
+/// Group consecutive sections so each group's text stays under `max_chars`
+fn batch_summaries(sections: Vec<TranscriptChunk>, max_chars: usize) -> Vec<Vec<TranscriptChunk>> {
+    let mut batches: Vec<Vec<TranscriptChunk>> = Vec::new();
+    let mut current: Vec<TranscriptChunk> = Vec::new();
+    let mut current_len = 0;
     
-    Ok(text_content)
+    for section in sections {
+        let len = section.text.len();
+        if !current.is_empty() && current_len + len > max_chars {
+            batches.push(std::mem::take(&mut current));
+            current_len = 0;
+        }
+        current_len += len;
+        current.push(section);
+    }
+    
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
 }
 
-async fn call_claude_cli(prompt: &str, image_paths: Option<Vec<String>>) -> Result<String, String> {
+/// Cut each section to an equal share of `max_chars`, keeping whole lines
+///
+/// Used when the summaries are too long to combine any further, so the
+/// final prompt still fits.
+fn shorten_summaries(sections: &mut [TranscriptChunk], max_chars: usize) {
+    let share = max_chars / sections.len().max(1);
+    for section in sections.iter_mut() {
+        // Leave room for the time range heading and the blank line between sections
+        let heading = section_text(std::slice::from_ref(section)).len() - section.text.trim().len() + 2;
+        let limit = share.saturating_sub(heading);
+        let text = section.text.trim();
+        if text.len() <= limit {
+            continue;
+        }
+        let mut end = limit;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(line_end) = text[..end].rfind('\n') {
+            end = line_end;
+        }
+        section.text = text[..end].trim_end().to_string();
+    }
+}
+
+pub(crate) async fn call_claude_cli(prompt: &str, image_paths: Option<Vec<String>>) -> Result<String, String> {
     let mut cmd = Command::new("claude");
     cmd.arg("-p").arg(prompt);
     
@@ -513,33 +1198,40 @@ pub async fn get_transcript_history(
     search: Option<String>,
 ) -> Result<Vec<TranscriptHistoryItem>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_history_items(&conn, limit.unwrap_or(20), offset.unwrap_or(0), search.as_deref())
+}
+
+/// History rows, newest first; `search` matches the title, channel or any transcript segment
+fn load_history_items(
+    conn: &rusqlite::Connection,
+    limit: i64,
+    offset: i64,
+    search: Option<&str>,
+) -> Result<Vec<TranscriptHistoryItem>, String> {
+    let pattern = search.map(|term| {
+        let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        format!("%{}%", escaped)
+    });
+    let segment_query = search.and_then(fts_query);
     
-    let limit = limit.unwrap_or(20);
-    let offset = offset.unwrap_or(0);
-    
-    let query = if let Some(search_term) = search {
-        format!(
-            "SELECT id, video_id, video_url, title, channel, duration, thumbnail_url, 
-             transcript_length, transcript_text, manifest_path, fetched_at
-             FROM transcript_history
-             WHERE title LIKE '%{}%' OR channel LIKE '%{}%' OR transcript_text LIKE '%{}%'
-             ORDER BY fetched_at DESC
-             LIMIT ? OFFSET ?",
-            search_term.replace("'", "''"),
-            search_term.replace("'", "''"),
-            search_term.replace("'", "''")
-        )
-    } else {
+    let mut stmt = conn.prepare(
         "SELECT id, video_id, video_url, title, channel, duration, thumbnail_url, 
-         transcript_length, transcript_text, manifest_path, fetched_at
+         transcript_length, transcript_text, manifest_path, fetched_at, provider, source_type, language_code,
+         is_machine_transcribed
          FROM transcript_history
+         WHERE ?1 IS NULL
+         OR title LIKE ?1 ESCAPE '\\'
+         OR channel LIKE ?1 ESCAPE '\\'
+         OR (?2 IS NOT NULL AND id IN (
+             SELECT s.history_id FROM transcript_segments_fts
+             JOIN transcript_segments s ON s.id = transcript_segments_fts.rowid
+             WHERE transcript_segments_fts MATCH ?2
+         ))
          ORDER BY fetched_at DESC
-         LIMIT ? OFFSET ?".to_string()
-    };
-    
-    let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
+         LIMIT ?3 OFFSET ?4"
+    ).map_err(|e| e.to_string())?;
     
-    let history_items = stmt.query_map(params![limit, offset], |row| {
+    let history_items = stmt.query_map(params![pattern, segment_query, limit, offset], |row| {
         let transcript_json: String = row.get(8)?;
         
         // Parse transcript to create preview
@@ -569,6 +1261,10 @@ pub async fn get_transcript_history(
             transcript_length: row.get(7)?,
             transcript_preview,
             manifest_path: row.get(9)?,
+            provider: row.get(11)?,
+            source_type: row.get(12)?,
+            language_code: row.get(13)?,
+            is_machine_transcribed: row.get(14)?,
             fetched_at: row.get(10)?,
         })
     }).map_err(|e| e.to_string())?;
@@ -588,15 +1284,23 @@ pub async fn get_transcript_history_details(
     history_id: i64,
 ) -> Result<TranscriptHistoryDetails, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    
+    load_transcript_details(&conn, history_id)
+}
+
+/// Load a transcript history row with its parsed segments
+pub(crate) fn load_transcript_details(
+    conn: &rusqlite::Connection,
+    history_id: i64,
+) -> Result<TranscriptHistoryDetails, String> {
     let mut stmt = conn.prepare(
         "SELECT id, video_id, video_url, title, channel, duration, thumbnail_url, 
-         transcript_text, manifest_path, fetched_at
+         transcript_text, manifest_path, fetched_at, provider, source_type, language_code,
+         chapters, chapter_source, is_machine_transcribed, cleaned_transcript, summary
          FROM transcript_history
          WHERE id = ?"
     ).map_err(|e| e.to_string())?;
     
-    let row = stmt.query_row(params![history_id], |row| {
+    let mut details = stmt.query_row(params![history_id], |row| {
         let transcript_json: String = row.get(7)?;
         
         // Parse transcript from JSON
@@ -613,6 +1317,8 @@ pub async fn get_transcript_history_details(
                     })
                     .collect()
             });
+        let chapters_json: Option<String> = row.get(13)?;
+        let cleaned_json: Option<String> = row.get(16)?;
         
         Ok(TranscriptHistoryDetails {
             id: row.get(0)?,
@@ -624,11 +1330,23 @@ pub async fn get_transcript_history_details(
             thumbnail_url: row.get(6)?,
             transcript: segments,
             manifest_path: row.get(8)?,
+            provider: row.get(10)?,
+            source_type: row.get(11)?,
+            language_code: row.get(12)?,
+            is_machine_transcribed: row.get(15)?,
+            chapters: chapters_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            chapter_source: row.get(14)?,
+            cleaned_transcript: cleaned_json.and_then(|json| serde_json::from_str(&json).ok()),
+            annotations: Vec::new(),
+            summary: row.get(17)?,
             fetched_at: row.get(9)?,
         })
     }).map_err(|e| format!("Failed to get transcript details: {}", e))?;
     
-    Ok(row)
+    details.annotations = load_annotations(conn, history_id)?;
+    Ok(details)
 }
 
 /// Delete transcript history entry
@@ -638,13 +1356,7 @@ pub async fn delete_transcript_history(
     history_id: i64,
 ) -> Result<(), String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    
-    conn.execute(
-        "DELETE FROM transcript_history WHERE id = ?",
-        params![history_id],
-    ).map_err(|e| format!("Failed to delete transcript history: {}", e))?;
-    
-    Ok(())
+    delete_history_rows(&conn, history_id)
 }
 
 // YouTube-specific commands for the YouTube Transcript component
@@ -652,10 +1364,10 @@ pub async fn delete_transcript_history(
 /// Fetch YouTube transcript (simpler version for YouTube tab)
 #[command]
 pub async fn fetch_youtube_transcript(
-    db: State<'_, AgentDb>,
+    app: AppHandle,
     url: String,
 ) -> Result<ProcessVideoResult, String> {
-    process_youtube_video(db, url, "~/VisionNotebook".to_string(), None).await
+    process_youtube_video(app, url, None, None, None, None).await
 }
 
 /// Get YouTube transcript history
@@ -693,4 +1405,168 @@ pub async fn get_youtube_transcript_details(
     transcript_id: i64,
 ) -> Result<TranscriptHistoryDetails, String> {
     get_transcript_history_details(db, transcript_id).await
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(text: &str, start_time: f64, end_time: f64) -> TranscriptChunk {
+        TranscriptChunk { text: text.to_string(), start_time, end_time, chapter: None }
+    }
+
+    #[test]
+    fn test_batch_summaries_keeps_order_and_limit() {
+        let sections = vec![
+            section("aaaa", 0.0, 600.0),
+            section("bbbb", 600.0, 1200.0),
+            section("cccccccc", 1200.0, 1800.0),
+            section("dd", 1800.0, 2400.0),
+        ];
+
+        let batches = batch_summaries(sections, 8);
+        let texts: Vec<Vec<&str>> = batches.iter()
+            .map(|b| b.iter().map(|s| s.text.as_str()).collect())
+            .collect();
+        assert_eq!(texts, vec![vec!["aaaa", "bbbb"], vec!["cccccccc"], vec!["dd"]]);
+    }
+
+    #[test]
+    fn test_shorten_summaries_fits_limit_on_whole_lines() {
+        let long = "- [00:10] é point\n".repeat(40);
+        let mut sections = vec![
+            section(&long, 0.0, 600.0),
+            section("- [10:05] short", 600.0, 1200.0),
+            section(&long, 1200.0, 1800.0),
+        ];
+
+        shorten_summaries(&mut sections, 300);
+        assert!(section_text(&sections).len() <= 300);
+        assert_eq!(sections[1].text, "- [10:05] short");
+        for cut in [&sections[0], &sections[2]] {
+            assert!(!cut.text.is_empty());
+            assert!(cut.text.lines().all(|line| line == "- [00:10] é point"));
+        }
+    }
+
+    #[test]
+    fn test_parse_quiz_questions_validates_against_chunk() {
+        let chunk = section("", 60.0, 120.5);
+        let question = |options: &str, answer: usize, timestamp: f64| format!(
+            r#"[{{"question": "Q?", "options": {}, "correct_answer": {}, "explanation": "E", "timestamp": {}}}]"#,
+            options, answer, timestamp
+        );
+        let four = r#"["a", "b", "c", "d"]"#;
+
+        let output = format!("Here you go:\n```json\n{}\n```", question(four, 3, 121.0));
+        assert_eq!(parse_quiz_questions(&output, &chunk).unwrap().len(), 1);
+
+        let err = parse_quiz_questions(&question(r#"["a", "b", "c"]"#, 0, 90.0), &chunk).unwrap_err();
+        assert_eq!(err, "Question 1 has 3 options; exactly 4 are required");
+
+        let err = parse_quiz_questions(&question(four, 4, 90.0), &chunk).unwrap_err();
+        assert!(err.contains("correct_answer 4"));
+
+        let err = parse_quiz_questions(&question(four, 0, 30.0), &chunk).unwrap_err();
+        assert_eq!(err, "Question 1 has timestamp 30; it must be between 60 and 121 seconds");
+    }
+
+    #[test]
+    fn test_chapter_chunks_stay_inside_chapters() {
+        let transcript: Vec<TranscriptSegment> = (0..6)
+            .map(|i| TranscriptSegment { text: format!("line {}", i), start: i as f64 * 30.0, duration: 30.0 })
+            .collect();
+        let chapters = vec![
+            Chapter { title: "Intro".to_string(), start: 0.0, end: Some(90.0) },
+            Chapter { title: "Moves".to_string(), start: 90.0, end: Some(180.0) },
+        ];
+        
+        let chunks = chapter_chunks(&transcript, &chapters, 10_000).unwrap();
+        let ranges: Vec<(f64, f64, Option<&str>)> = chunks.iter()
+            .map(|c| (c.start_time, c.end_time, c.chapter.as_deref()))
+            .collect();
+        assert_eq!(ranges, vec![(0.0, 90.0, Some("Intro")), (90.0, 180.0, Some("Moves"))]);
+        assert_eq!(chunks[1].text, "[01:30] line 3 [02:00] line 4 [02:30] line 5 ");
+        assert_eq!(section_text(&chunks[..1]), "[00:00 - 01:30] Intro\n[00:00] line 0 [00:30] line 1 [01:00] line 2");
+        
+        // Without chapters the whole transcript is chunked by size
+        assert_eq!(chapter_chunks(&transcript, &[], 10_000).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_question_quotas_cover_every_chunk() {
+        assert_eq!(question_quotas(5, 1), vec![5]);
+        assert_eq!(question_quotas(10, 4), vec![3, 3, 2, 2]);
+        assert_eq!(question_quotas(2, 5), vec![1, 1, 0, 0, 0]);
+        assert!(question_quotas(5, 0).is_empty());
+    }
+    
+    #[test]
+    fn test_history_search_is_parameterized() {
+        let conn = crate::commands::migrations::open_test_db();
+        let video = |video_id: &str, title: &str| VideoInfo {
+            video_id: video_id.to_string(),
+            title: title.to_string(),
+            channel: "Crab Academy".to_string(),
+            duration: None,
+            thumbnail_url: None,
+        };
+        let segments = vec![TranscriptSegment { text: "the borrow checker".to_string(), start: 3.0, duration: 2.0 }];
+        save_transcript_history(&conn, &video("aaaaaaaaaaa", "It's 100% Rust"), "u", &segments, Path::new("m"), None, "youtube").unwrap();
+        save_transcript_history(&conn, &video("bbbbbbbbbbb", "Go basics"), "u", &[], Path::new("m"), None, "youtube").unwrap();
+        
+        let titles = |search: Option<&str>| -> Vec<String> {
+            load_history_items(&conn, 10, 0, search).unwrap().into_iter().map(|item| item.title).collect()
+        };
+        assert_eq!(titles(Some("It's")), vec!["It's 100% Rust"]);
+        assert_eq!(titles(Some("100%")), vec!["It's 100% Rust"]);
+        // Matches inside the transcript come from the segment index
+        assert_eq!(titles(Some("checker")), vec!["It's 100% Rust"]);
+        assert_eq!(titles(Some("%")).len(), 1);
+        assert!(titles(Some("' OR 1=1 --")).is_empty());
+        assert_eq!(titles(None).len(), 2);
+    }
+    
+    #[test]
+    fn test_section_text_anchors_time_ranges() {
+        let sections = vec![section(" - [00:05] intro\n", 0.0, 600.0), section("- [1:00:10] outro", 3600.0, 3700.0)];
+        assert_eq!(
+            section_text(&sections),
+            "[00:00 - 10:00]\n- [00:05] intro\n\n[1:00:00 - 1:01:40]\n- [1:00:10] outro"
+        );
+    }
+    
+    #[test]
+    fn test_saved_transcripts_have_one_notebook_entry_until_deleted() {
+        let conn = crate::commands::migrations::open_test_db();
+        let video_info = VideoInfo {
+            video_id: "local-abc123".to_string(),
+            title: "Imported lecture".to_string(),
+            channel: "Local import".to_string(),
+            duration: None,
+            thumbnail_url: None,
+        };
+        let segments = vec![TranscriptSegment { text: "borrowing rules".to_string(), start: 0.0, duration: 2.0 }];
+        let save = || save_transcript_history(
+            &conn,
+            &video_info,
+            "file:///tmp/lecture.srt",
+            &segments,
+            Path::new("/tmp/local-abc123/manifest.json"),
+            Some("subtitle_file"),
+            "subtitle_file",
+        ).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+        };
+        let history_id = save();
+        assert_eq!(count("search_index"), 1);
+        // Saving again replaces the entry
+        assert_eq!(save(), history_id);
+        assert_eq!(count("notebook_entries"), 1);
+        
+        delete_history_rows(&conn, history_id).unwrap();
+        assert_eq!(count("search_index"), 0);
+        assert_eq!(count("notebook_entries"), 0);
+        assert_eq!(count("transcript_history"), 0);
+    }
+}
@@ -0,0 +1,99 @@
+use rusqlite::{params, Connection};
+use tauri::{command, State};
+use crate::commands::agents::AgentDb;
+use crate::transcript::whisper::{SharedWhisperConfig, WhisperConfig};
+
+const BINARY_SETTING: &str = "whisper_binary_path";
+const MODEL_SETTING: &str = "whisper_model_path";
+
+/// Local transcription settings, shared with the whisper transcript provider
+pub struct WhisperConfigState(pub SharedWhisperConfig);
+
+/// Get the whisper binary and model used for videos without captions
+#[command]
+pub async fn get_whisper_config(state: State<'_, WhisperConfigState>) -> Result<WhisperConfig, String> {
+    Ok(state.0.read().map_err(|e| e.to_string())?.clone())
+}
+
+/// Set the whisper binary and model; empty values clear them
+///
+/// The model must exist. The binary defaults to `whisper-cli` on PATH.
+#[command]
+pub async fn set_whisper_config(
+    db: State<'_, AgentDb>,
+    state: State<'_, WhisperConfigState>,
+    binary_path: Option<String>,
+    model_path: Option<String>,
+) -> Result<WhisperConfig, String> {
+    let config = WhisperConfig {
+        binary_path: binary_path.map(|path| path.trim().to_string()).filter(|path| !path.is_empty()),
+        model_path: model_path.map(|path| path.trim().to_string()).filter(|path| !path.is_empty()),
+    };
+    if config.model_path.is_some() {
+        config.model()?;
+    }
+
+    {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        save_whisper_config(&conn, &config)?;
+    }
+    *state.0.write().map_err(|e| e.to_string())? = config.clone();
+    Ok(config)
+}
+
+// Helper functions
+
+/// Read the saved whisper settings
+pub fn load_whisper_config(conn: &Connection) -> WhisperConfig {
+    let setting = |key: &str| {
+        conn.query_row(
+            "SELECT value FROM app_settings WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        ).ok()
+    };
+
+    WhisperConfig {
+        binary_path: setting(BINARY_SETTING),
+        model_path: setting(MODEL_SETTING),
+    }
+}
+
+fn save_whisper_config(conn: &Connection, config: &WhisperConfig) -> Result<(), String> {
+    for (key, value) in [(BINARY_SETTING, &config.binary_path), (MODEL_SETTING, &config.model_path)] {
+        match value {
+            Some(value) => conn.execute(
+                "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = ?2",
+                params![key, value],
+            ),
+            None => conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key]),
+        }
+        .map_err(|e| format!("Failed to save whisper settings: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::open_test_db;
+
+    #[test]
+    fn test_whisper_config_round_trip() {
+        let conn = open_test_db();
+        assert!(load_whisper_config(&conn).model_path.is_none());
+
+        let config = WhisperConfig {
+            binary_path: Some("/opt/whisper.cpp/whisper-cli".to_string()),
+            model_path: Some("/opt/models/ggml-base.en.bin".to_string()),
+        };
+        save_whisper_config(&conn, &config).unwrap();
+        let loaded = load_whisper_config(&conn);
+        assert_eq!(loaded.binary_path, config.binary_path);
+        assert_eq!(loaded.model_path, config.model_path);
+
+        save_whisper_config(&conn, &WhisperConfig { binary_path: None, ..config }).unwrap();
+        assert_eq!(load_whisper_config(&conn).binary_path, None);
+    }
+}
@@ -0,0 +1,319 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::time::Duration;
+
+/// Delay before the first retry; doubled for each later attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+/// Longest wait between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// Attempts a job gets before it is marked failed
+pub const DEFAULT_MAX_ATTEMPTS: i64 = 3;
+
+const JOB_COLUMNS: &str = "id, kind, params, status, stage, checkpoint, attempts, max_attempts,
+    run_after, last_error, result, created_at, updated_at";
+
+/// Lifecycle of a queued job
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for the worker, possibly until `run_after` for a retry
+    Queued,
+    Running,
+    /// Out of attempts
+    Failed,
+    Done,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Failed => "failed",
+            JobStatus::Done => "done",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(status: &str) -> Result<Self, String> {
+        match status {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "failed" => Ok(JobStatus::Failed),
+            "done" => Ok(JobStatus::Done),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            other => Err(format!("Unknown job status: {}", other)),
+        }
+    }
+}
+
+/// A unit of background work and how far it got
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: i64,
+    /// What the job does, e.g. "youtube_video"
+    pub kind: String,
+    pub params: JsonValue,
+    pub status: JobStatus,
+    /// Stage running or last started
+    pub stage: Option<String>,
+    /// Output of finished stages; large, so not sent to the frontend
+    #[serde(skip_serializing)]
+    pub checkpoint: Option<JsonValue>,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    /// Earliest time (UTC, SQLite format) a queued job may start
+    pub run_after: String,
+    pub last_error: Option<String>,
+    pub result: Option<JsonValue>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Add a job to the queue and return it
+pub fn enqueue(conn: &Connection, kind: &str, params: &JsonValue, max_attempts: i64) -> Result<Job, String> {
+    conn.execute(
+        "INSERT INTO jobs (kind, params, max_attempts) VALUES (?1, ?2, ?3)",
+        params![kind, params.to_string(), max_attempts.max(1)],
+    ).map_err(|e| format!("Failed to enqueue job: {}", e))?;
+    load(conn, conn.last_insert_rowid())
+}
+
+pub fn load(conn: &Connection, id: i64) -> Result<Job, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+        params![id],
+        job_from_row,
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Job {} not found", id))
+}
+
+/// Jobs newest first, optionally only those in `status`
+pub fn list(conn: &Connection, status: Option<JobStatus>, limit: i64) -> Result<Vec<Job>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM jobs WHERE (?1 IS NULL OR status = ?1) ORDER BY id DESC LIMIT ?2",
+        JOB_COLUMNS
+    )).map_err(|e| e.to_string())?;
+
+    let jobs = stmt.query_map(params![status.map(|s| s.as_str()), limit], job_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(jobs)
+}
+
+/// Mark the oldest due job running and return it
+pub fn claim_next(conn: &Connection) -> Result<Option<Job>, String> {
+    let id: Option<i64> = conn.query_row(
+        "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = CURRENT_TIMESTAMP
+         WHERE id = (
+             SELECT id FROM jobs
+             WHERE status = 'queued' AND run_after <= CURRENT_TIMESTAMP
+             ORDER BY run_after, id
+             LIMIT 1
+         )
+         RETURNING id",
+        [],
+        |row| row.get(0),
+    ).optional()
+        .map_err(|e| format!("Failed to claim job: {}", e))?;
+
+    id.map(|id| load(conn, id)).transpose()
+}
+
+/// Record the stage a running job entered and the output of earlier stages
+///
+/// Fails once the job is no longer running, so a cancelled job stops at its
+/// next stage.
+pub fn save_checkpoint(conn: &Connection, id: i64, stage: &str, checkpoint: &JsonValue) -> Result<(), String> {
+    let updated = conn.execute(
+        "UPDATE jobs SET stage = ?1, checkpoint = ?2, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?3 AND status = 'running'",
+        params![stage, checkpoint.to_string(), id],
+    ).map_err(|e| format!("Failed to save job checkpoint: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Job {} is no longer running", id));
+    }
+    Ok(())
+}
+
+/// Finish a running job; a job cancelled meanwhile stays cancelled
+pub fn complete(conn: &Connection, id: i64, result: &JsonValue) -> Result<(), String> {
+    conn.execute(
+        "UPDATE jobs SET status = 'done', result = ?1, last_error = NULL, updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?2 AND status = 'running'",
+        params![result.to_string(), id],
+    ).map_err(|e| format!("Failed to complete job: {}", e))?;
+    Ok(())
+}
+
+/// Record a failed attempt: requeue with backoff, or fail once out of attempts
+pub fn fail(conn: &Connection, id: i64, error: &str) -> Result<JobStatus, String> {
+    let job = load(conn, id)?;
+    if job.status != JobStatus::Running {
+        return Ok(job.status);
+    }
+
+    if job.attempts < job.max_attempts {
+        let delay = retry_delay(job.attempts).as_secs();
+        conn.execute(
+            "UPDATE jobs SET status = 'queued', last_error = ?1,
+                 run_after = datetime('now', ?2), updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?3",
+            params![error, format!("+{} seconds", delay), id],
+        ).map_err(|e| format!("Failed to requeue job: {}", e))?;
+        Ok(JobStatus::Queued)
+    } else {
+        conn.execute(
+            "UPDATE jobs SET status = 'failed', last_error = ?1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2",
+            params![error, id],
+        ).map_err(|e| format!("Failed to update job: {}", e))?;
+        Ok(JobStatus::Failed)
+    }
+}
+
+/// Cancel a queued or running job; finished jobs are left as they are
+pub fn cancel(conn: &Connection, id: i64) -> Result<Job, String> {
+    conn.execute(
+        "UPDATE jobs SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1 AND status IN ('queued', 'running')",
+        params![id],
+    ).map_err(|e| format!("Failed to cancel job: {}", e))?;
+    load(conn, id)
+}
+
+/// Queue a failed or cancelled job again with fresh attempts
+///
+/// The checkpoint is kept, so finished stages are not repeated.
+pub fn retry(conn: &Connection, id: i64) -> Result<Job, String> {
+    let job = load(conn, id)?;
+    if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+        return Err(format!("Job {} is {} and cannot be retried", id, job.status.as_str()));
+    }
+
+    conn.execute(
+        "UPDATE jobs SET status = 'queued', attempts = 0, run_after = CURRENT_TIMESTAMP,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+        params![id],
+    ).map_err(|e| format!("Failed to retry job: {}", e))?;
+    load(conn, id)
+}
+
+/// Queue jobs left running by a previous session so they resume
+///
+/// The interrupted attempt is not counted against the job.
+pub fn requeue_interrupted(conn: &Connection) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE jobs SET status = 'queued', attempts = MAX(attempts - 1, 0),
+             run_after = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+         WHERE status = 'running'",
+        [],
+    ).map_err(|e| format!("Failed to resume jobs: {}", e))
+}
+
+/// Wait before retrying after the given number of attempts
+pub fn retry_delay(attempts: i64) -> Duration {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    RETRY_BASE_DELAY.saturating_mul(2u32.pow(exponent)).min(RETRY_MAX_DELAY)
+}
+
+fn job_from_row(row: &Row) -> rusqlite::Result<Job> {
+    let json = |index: usize| -> rusqlite::Result<Option<JsonValue>> {
+        let text: Option<String> = row.get(index)?;
+        Ok(text.and_then(|text| serde_json::from_str(&text).ok()))
+    };
+    let status: String = row.get(3)?;
+
+    Ok(Job {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        params: json(2)?.unwrap_or(JsonValue::Null),
+        status: JobStatus::parse(&status).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, e.into())
+        })?,
+        stage: row.get(4)?,
+        checkpoint: json(5)?,
+        attempts: row.get(6)?,
+        max_attempts: row.get(7)?,
+        run_after: row.get(8)?,
+        last_error: row.get(9)?,
+        result: json(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::migrations::open_test_db;
+    use serde_json::json;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(40), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let conn = open_test_db();
+        let job = enqueue(&conn, "youtube_video", &json!({"url": "https://youtu.be/abcDEF12345"}), 2).unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let claimed = claim_next(&conn).unwrap().unwrap();
+        assert_eq!((claimed.id, claimed.status, claimed.attempts), (job.id, JobStatus::Running, 1));
+        assert!(claim_next(&conn).unwrap().is_none());
+
+        save_checkpoint(&conn, job.id, "download", &json!({"transcript": []})).unwrap();
+
+        // First failure waits for a retry, the second uses up the attempts
+        assert_eq!(fail(&conn, job.id, "yt-dlp failed").unwrap(), JobStatus::Queued);
+        assert!(claim_next(&conn).unwrap().is_none());
+        conn.execute("UPDATE jobs SET run_after = datetime('now', '-1 seconds')", []).unwrap();
+        let claimed = claim_next(&conn).unwrap().unwrap();
+        assert_eq!(claimed.stage.as_deref(), Some("download"));
+        assert_eq!(claimed.checkpoint, Some(json!({"transcript": []})));
+        assert_eq!(fail(&conn, job.id, "yt-dlp failed again").unwrap(), JobStatus::Failed);
+
+        let retried = retry(&conn, job.id).unwrap();
+        assert_eq!((retried.status, retried.attempts), (JobStatus::Queued, 0));
+        claim_next(&conn).unwrap().unwrap();
+        complete(&conn, job.id, &json!({"manifest_path": "/tmp/manifest.json"})).unwrap();
+        let done = load(&conn, job.id).unwrap();
+        assert_eq!((done.status, done.last_error), (JobStatus::Done, None));
+        assert!(retry(&conn, job.id).is_err());
+    }
+
+    #[test]
+    fn test_cancel_and_resume() {
+        let conn = open_test_db();
+        let first = enqueue(&conn, "youtube_video", &json!({}), DEFAULT_MAX_ATTEMPTS).unwrap();
+        let second = enqueue(&conn, "youtube_video", &json!({}), DEFAULT_MAX_ATTEMPTS).unwrap();
+
+        assert_eq!(cancel(&conn, second.id).unwrap().status, JobStatus::Cancelled);
+        claim_next(&conn).unwrap().unwrap();
+
+        // The app stopped while the first job was running
+        assert_eq!(requeue_interrupted(&conn).unwrap(), 1);
+        let resumed = load(&conn, first.id).unwrap();
+        assert_eq!((resumed.status, resumed.attempts), (JobStatus::Queued, 0));
+
+        // Cancelling a running job wins over its late result
+        claim_next(&conn).unwrap().unwrap();
+        cancel(&conn, first.id).unwrap();
+        assert!(save_checkpoint(&conn, first.id, "download", &json!({})).is_err());
+        complete(&conn, first.id, &json!({})).unwrap();
+        assert_eq!(load(&conn, first.id).unwrap().status, JobStatus::Cancelled);
+        assert_eq!(list(&conn, Some(JobStatus::Cancelled), 10).unwrap().len(), 2);
+    }
+}
@@ -1,10 +1,19 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod anki;
+mod chapters;
 mod checkpoint;
 mod claude_binary;
 mod commands;
+mod jobs;
+mod manifest;
+mod media;
 mod process;
+mod progress;
+mod structured_output;
+mod transcript;
+mod vault;
 
 use checkpoint::state::CheckpointState;
 use commands::agents::{
@@ -43,12 +52,38 @@ use commands::storage::{
     storage_insert_row, storage_execute_sql, storage_reset_database,
 };
 use commands::vision_notebook::{
-    extract_video_id, process_youtube_video, generate_quiz_from_transcript,
+    extract_video_id, process_youtube_video, process_local_video, generate_quiz_from_transcript,
     ask_video_question, summarize_video, get_transcript_history,
     get_transcript_history_details, delete_transcript_history,
     fetch_youtube_transcript, get_youtube_transcript_history,
     search_youtube_transcripts, delete_youtube_transcript,
-    get_youtube_transcript_details,
+    get_youtube_transcript_details, get_transcript_providers,
+    set_transcript_provider_order,
+};
+use commands::subtitles::{export_transcript_subtitles, import_subtitle_file};
+use commands::quiz::{
+    delete_quiz, get_quiz_attempts, get_quiz_scores, get_quizzes, save_quiz, submit_quiz_attempt,
+};
+use commands::transcript_tracks::{
+    add_transcript_track, list_caption_languages, list_transcript_tracks,
+    set_primary_transcript_track,
+};
+use commands::chapters::{detect_video_chapters, get_video_chapters};
+use commands::search::search_transcript_segments;
+use commands::ingest::{expand_youtube_collection, ingest_youtube_collection};
+use commands::jobs::{
+    cancel_job, enqueue_youtube_video_job, get_job, list_jobs, retry_job, start_job_worker,
+    JobQueueState,
+};
+use commands::whisper::{get_whisper_config, load_whisper_config, set_whisper_config, WhisperConfigState};
+use commands::cleanup::clean_transcript;
+use commands::annotations::{add_annotation, delete_annotation, get_annotations, update_annotation};
+use commands::clips::{add_clip_bookmark, delete_clip_bookmark, export_clip, get_clip_bookmarks, update_clip_bookmark};
+use commands::vault::export_video_to_vault;
+use commands::library::{get_library_disk_usage, get_library_root, relocate_library, set_library_root};
+use commands::review::{
+    create_review_cards, delete_review_card, export_anki_deck, get_due_review_cards,
+    grade_review_card,
 };
 use commands::history::{
     save_claude_session, get_claude_sessions, get_project_history,
@@ -56,7 +91,8 @@ use commands::history::{
     update_entry_tags, cleanup_old_sessions,
 };
 use process::ProcessRegistryState;
-use std::sync::Mutex;
+use transcript::TranscriptProviderState;
+use std::sync::{Arc, Mutex, RwLock};
 use tauri::Manager;
 
 fn main() {
@@ -99,6 +135,19 @@ fn main() {
             // Initialize Claude process state
             app.manage(ClaudeProcessState::default());
 
+            // Initialize transcript providers, with local transcription as the fallback
+            let whisper_config = {
+                let db = app.state::<AgentDb>();
+                let conn = db.0.lock().expect("Failed to lock agents database");
+                Arc::new(RwLock::new(load_whisper_config(&conn)))
+            };
+            app.manage(TranscriptProviderState::new(whisper_config.clone()));
+            app.manage(WhisperConfigState(whisper_config));
+
+            // Start the background job queue, resuming interrupted jobs
+            app.manage(JobQueueState::default());
+            start_job_worker(app.handle().clone());
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -212,12 +261,59 @@ fn main() {
             // Vision Notebook Commands
             extract_video_id,
             process_youtube_video,
+            process_local_video,
             generate_quiz_from_transcript,
             ask_video_question,
             summarize_video,
             get_transcript_history,
             get_transcript_history_details,
             delete_transcript_history,
+            get_transcript_providers,
+            set_transcript_provider_order,
+            export_transcript_subtitles,
+            import_subtitle_file,
+            list_caption_languages,
+            list_transcript_tracks,
+            add_transcript_track,
+            set_primary_transcript_track,
+            save_quiz,
+            get_quizzes,
+            delete_quiz,
+            submit_quiz_attempt,
+            get_quiz_attempts,
+            get_quiz_scores,
+            create_review_cards,
+            get_due_review_cards,
+            grade_review_card,
+            delete_review_card,
+            export_anki_deck,
+            get_video_chapters,
+            detect_video_chapters,
+            search_transcript_segments,
+            expand_youtube_collection,
+            ingest_youtube_collection,
+            enqueue_youtube_video_job,
+            get_job,
+            list_jobs,
+            cancel_job,
+            retry_job,
+            get_whisper_config,
+            set_whisper_config,
+            clean_transcript,
+            add_annotation,
+            update_annotation,
+            delete_annotation,
+            get_annotations,
+            add_clip_bookmark,
+            update_clip_bookmark,
+            delete_clip_bookmark,
+            get_clip_bookmarks,
+            export_clip,
+            export_video_to_vault,
+            get_library_root,
+            set_library_root,
+            relocate_library,
+            get_library_disk_usage,
             
             // YouTube Transcript Commands
             fetch_youtube_transcript,
@@ -0,0 +1,502 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::Path;
+
+use crate::commands::vision_notebook::{TranscriptSegment, VideoInfo};
+use crate::media::{FrameSampling, SampledFrame};
+use crate::transcript::subtitles::format_clock;
+
+/// Version written by this build; older manifests are upgraded when loaded
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// The `manifest.json` written next to each processed video
+///
+/// External tools read these files, so fields are only ever added.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub video: VideoInfo,
+    /// Transcript provider that produced the text items
+    pub provider: Option<String>,
+    /// How the image items were sampled, if any were
+    pub frame_sampling: Option<FrameSampling>,
+    /// RFC 3339 creation time
+    pub created_at: String,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+    pub items: Vec<ManifestItem>,
+    /// Transcript as fetched, kept when the text items hold a cleaned-up version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub raw_transcript: Vec<TranscriptSegment>,
+}
+
+/// A titled section of the video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+/// One timed entry in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ManifestItem {
+    Text {
+        t: f64,
+        #[serde(default)]
+        duration: f64,
+        text: String,
+    },
+    Image {
+        t: f64,
+        path: String,
+    },
+}
+
+impl ManifestItem {
+    /// Seconds from the start of the video
+    pub fn t(&self) -> f64 {
+        match self {
+            ManifestItem::Text { t, .. } | ManifestItem::Image { t, .. } => *t,
+        }
+    }
+}
+
+/// Limits on how much of a manifest goes into a prompt
+#[derive(Debug, Clone, Copy)]
+pub struct ContextBudget {
+    pub max_chars: usize,
+    pub max_frames: usize,
+}
+
+/// Transcript lines and frames chosen for a prompt
+#[derive(Debug, Default)]
+pub struct ManifestContext {
+    /// `[MM:SS] text` lines in time order
+    pub text: String,
+    pub image_paths: Vec<String>,
+}
+
+/// Version 1 manifests were a bare array of these
+#[derive(Deserialize)]
+struct ManifestItemV1 {
+    t: f64,
+    kind: String,
+    text: Option<String>,
+    path: Option<String>,
+}
+
+impl Manifest {
+    /// Build a manifest from a transcript and sampled frames, ordered by time
+    pub fn build(
+        video: &VideoInfo,
+        transcript: &[TranscriptSegment],
+        frames: &[SampledFrame],
+        provider: Option<&str>,
+        frame_sampling: Option<FrameSampling>,
+    ) -> Self {
+        let mut items: Vec<ManifestItem> = transcript
+            .iter()
+            .map(|segment| ManifestItem::Text {
+                t: segment.start,
+                duration: segment.duration,
+                text: segment.text.clone(),
+            })
+            .collect();
+
+        items.extend(frames.iter().map(|frame| ManifestItem::Image {
+            t: frame.t,
+            path: frame.path.to_string_lossy().to_string(),
+        }));
+
+        let mut manifest = Self {
+            version: MANIFEST_VERSION,
+            video: video.clone(),
+            provider: provider.map(|p| p.to_string()),
+            frame_sampling,
+            created_at: Utc::now().to_rfc3339(),
+            chapters: Vec::new(),
+            items,
+            raw_transcript: Vec::new(),
+        };
+        manifest.sort_items();
+        manifest
+    }
+
+    /// Read a manifest file, upgrading older versions
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+
+        // v1 files carry no video metadata; the directory is named after the video id
+        let video_id = path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::parse(&content, &video_id)
+            .map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))
+    }
+
+    /// Parse manifest JSON of any supported version
+    pub fn parse(content: &str, video_id: &str) -> Result<Self, String> {
+        let value: JsonValue = serde_json::from_str(content)
+            .map_err(|e| format!("not valid JSON: {}", e))?;
+
+        let manifest = match &value {
+            JsonValue::Array(_) => Self::upgrade_v1(value, video_id)?,
+            JsonValue::Object(object) => {
+                let version = object
+                    .get("version")
+                    .ok_or("missing \"version\"")?
+                    .as_u64()
+                    .ok_or("\"version\" must be a positive integer")?;
+                if version < 2 || version > MANIFEST_VERSION as u64 {
+                    return Err(format!(
+                        "unsupported version {} (expected 1 to {})",
+                        version, MANIFEST_VERSION
+                    ));
+                }
+                serde_json::from_value(value).map_err(|e| e.to_string())?
+            }
+            _ => return Err("expected an object or a version 1 array".to_string()),
+        };
+
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Write the manifest as pretty-printed JSON
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to save manifest: {}", e))
+    }
+
+    /// Transcript segments in time order
+    pub fn transcript(&self) -> Vec<TranscriptSegment> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                ManifestItem::Text { t, duration, text } => Some(TranscriptSegment {
+                    text: text.clone(),
+                    start: *t,
+                    duration: *duration,
+                }),
+                ManifestItem::Image { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Path of the frame on screen at `t`: the last one sampled at or before it
+    pub fn frame_at(&self, t: f64) -> Option<&str> {
+        let mut frames = self.items.iter().filter_map(|item| match item {
+            ManifestItem::Image { t, path } => Some((*t, path.as_str())),
+            ManifestItem::Text { .. } => None,
+        });
+        let first = frames.next()?;
+        Some(frames.take_while(|(frame_t, _)| *frame_t <= t).last().unwrap_or(first).1)
+    }
+
+    /// Select transcript lines and frames within `window` seconds of `focus`
+    ///
+    /// Lines closest to the focus are kept when the text exceeds the budget, and
+    /// the frames nearest to it are attached. Without a focus the whole video is
+    /// used: text from the start, frames spread evenly.
+    pub fn select_context(&self, focus: Option<f64>, window: f64, budget: ContextBudget) -> ManifestContext {
+        let (start, end) = match focus {
+            Some(t) => ((t - window).max(0.0), t + window),
+            None => (0.0, f64::INFINITY),
+        };
+        let distance = |t: f64| focus.map_or(t, |focus| (t - focus).abs());
+
+        let mut lines: Vec<(f64, String)> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ManifestItem::Text { t, duration, text } if *t <= end && t + duration >= start => {
+                    Some((*t, format!("[{}] {}", format_clock(*t), text)))
+                }
+                _ => None,
+            })
+            .collect();
+        lines.sort_by(|a, b| distance(a.0).partial_cmp(&distance(b.0)).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut used = 0;
+        let mut kept: Vec<(f64, String)> = Vec::new();
+        for (t, line) in lines {
+            used += line.len() + 1;
+            if used > budget.max_chars {
+                break;
+            }
+            kept.push((t, line));
+        }
+        kept.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut frames: Vec<(f64, &str)> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ManifestItem::Image { t, path } if *t >= start && *t <= end => Some((*t, path.as_str())),
+                _ => None,
+            })
+            .collect();
+        let frames: Vec<(f64, &str)> = match focus {
+            Some(_) => {
+                frames.sort_by(|a, b| distance(a.0).partial_cmp(&distance(b.0)).unwrap_or(std::cmp::Ordering::Equal));
+                frames.truncate(budget.max_frames);
+                frames.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                frames
+            }
+            None => spread(&frames, budget.max_frames),
+        };
+
+        ManifestContext {
+            text: kept.into_iter().map(|(_, line)| line).collect::<Vec<_>>().join("\n"),
+            image_paths: frames.into_iter().map(|(_, path)| path.to_string()).collect(),
+        }
+    }
+
+    /// Replace the text items with another transcript, keeping frames
+    pub fn replace_transcript(&mut self, transcript: &[TranscriptSegment], provider: Option<&str>) {
+        self.items.retain(|item| matches!(item, ManifestItem::Image { .. }));
+        self.items.extend(transcript.iter().map(|segment| ManifestItem::Text {
+            t: segment.start,
+            duration: segment.duration,
+            text: segment.text.clone(),
+        }));
+        self.provider = provider.map(|p| p.to_string());
+        self.raw_transcript.clear();
+        self.sort_items();
+    }
+
+    /// Replace the text items with a cleaned-up transcript, keeping the raw one
+    ///
+    /// Cleaning again starts from the kept raw transcript, not the last result.
+    pub fn apply_cleaned_transcript(&mut self, cleaned: &[TranscriptSegment]) {
+        if self.raw_transcript.is_empty() {
+            self.raw_transcript = self.transcript();
+        }
+        self.items.retain(|item| matches!(item, ManifestItem::Image { .. }));
+        self.items.extend(cleaned.iter().map(|segment| ManifestItem::Text {
+            t: segment.start,
+            duration: segment.duration,
+            text: segment.text.clone(),
+        }));
+        self.sort_items();
+    }
+
+    fn sort_items(&mut self) {
+        self.items
+            .sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    fn upgrade_v1(value: JsonValue, video_id: &str) -> Result<Self, String> {
+        let legacy: Vec<ManifestItemV1> = serde_json::from_value(value)
+            .map_err(|e| format!("invalid version 1 item: {}", e))?;
+
+        let items = legacy
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| match (item.kind.as_str(), item.text, item.path) {
+                ("text", Some(text), _) => Ok(ManifestItem::Text { t: item.t, duration: 0.0, text }),
+                ("image", _, Some(path)) => Ok(ManifestItem::Image { t: item.t, path }),
+                ("text", None, _) => Err(format!("item {} is a text item without text", i)),
+                ("image", _, None) => Err(format!("item {} is an image item without a path", i)),
+                (kind, _, _) => Err(format!("item {} has unknown kind \"{}\"", i, kind)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            video: VideoInfo {
+                video_id: video_id.to_string(),
+                title: video_id.to_string(),
+                channel: String::new(),
+                duration: None,
+                thumbnail_url: None,
+            },
+            provider: None,
+            frame_sampling: None,
+            created_at: Utc::now().to_rfc3339(),
+            chapters: Vec::new(),
+            items,
+            raw_transcript: Vec::new(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (i, item) in self.items.iter().enumerate() {
+            let t = item.t();
+            if !t.is_finite() || t < 0.0 {
+                return Err(format!("item {} has invalid timestamp {}", i, t));
+            }
+            if let ManifestItem::Image { path, .. } = item {
+                if path.is_empty() {
+                    return Err(format!("item {} is an image item without a path", i));
+                }
+            }
+        }
+
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            if !chapter.start.is_finite() || chapter.start < 0.0 {
+                return Err(format!("chapter {} has invalid start {}", i, chapter.start));
+            }
+            if chapter.end.is_some_and(|end| end < chapter.start) {
+                return Err(format!("chapter {} ends before it starts", i));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Pick up to `count` evenly spaced elements, keeping the first and last
+fn spread<T: Copy>(items: &[T], count: usize) -> Vec<T> {
+    match count {
+        0 => Vec::new(),
+        _ if items.len() <= count => items.to_vec(),
+        1 => vec![items[items.len() / 2]],
+        _ => (0..count).map(|i| items[i * (items.len() - 1) / (count - 1)]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = include_str!("../tests/fixtures/manifest/v1.json");
+    const V2: &str = include_str!("../tests/fixtures/manifest/v2.json");
+
+    #[test]
+    fn test_upgrade_v1_manifest() {
+        let manifest = Manifest::parse(V1, "dQw4w9WgXcQ").unwrap();
+
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        assert_eq!(manifest.video.video_id, "dQw4w9WgXcQ");
+        assert_eq!(manifest.items.len(), 4);
+        assert!(matches!(&manifest.items[1], ManifestItem::Image { t, .. } if *t == 1.0));
+        let transcript = manifest.transcript();
+        assert_eq!(transcript.len(), 3);
+        assert_eq!(transcript[2].text, "let's get started");
+        assert_eq!(transcript[2].start, 6.1);
+    }
+
+    #[test]
+    fn test_parse_v2_manifest() {
+        let manifest = Manifest::parse(V2, "ignored").unwrap();
+
+        assert_eq!(manifest.video.title, "Rust Ownership Explained");
+        assert_eq!(manifest.provider.as_deref(), Some("youtube_captions"));
+        assert_eq!(manifest.frame_sampling, Some(FrameSampling::Fps { fps: 0.5 }));
+        assert_eq!(manifest.chapters.len(), 2);
+        assert!(matches!(&manifest.items[0], ManifestItem::Text { duration, .. } if *duration == 2.18));
+
+        // Round-trips through the current writer
+        let json = serde_json::to_string(&manifest).unwrap();
+        let reparsed = Manifest::parse(&json, "ignored").unwrap();
+        assert_eq!(reparsed.items.len(), manifest.items.len());
+    }
+
+    fn long_manifest() -> Manifest {
+        let video = VideoInfo {
+            video_id: "stream".to_string(),
+            title: "Stream".to_string(),
+            channel: "Channel".to_string(),
+            duration: None,
+            thumbnail_url: None,
+        };
+        // Three hours: a caption every 10s and a frame every 2s
+        let transcript: Vec<TranscriptSegment> = (0..1080)
+            .map(|i| TranscriptSegment {
+                text: format!("line {}", i),
+                start: i as f64 * 10.0,
+                duration: 10.0,
+            })
+            .collect();
+        let frames: Vec<SampledFrame> = (0..5400)
+            .map(|i| SampledFrame {
+                path: format!("frame_{:06}.jpg", i).into(),
+                t: i as f64 * 2.0,
+            })
+            .collect();
+        Manifest::build(&video, &transcript, &frames, None, None)
+    }
+
+    #[test]
+    fn test_select_context_around_focus() {
+        let manifest = long_manifest();
+        let budget = ContextBudget { max_chars: 10_000, max_frames: 3 };
+
+        let context = manifest.select_context(Some(2820.0), 30.0, budget);
+        let lines: Vec<&str> = context.text.lines().collect();
+        assert_eq!(lines.first(), Some(&"[46:20] line 278"));
+        assert_eq!(lines.last(), Some(&"[47:30] line 285"));
+        assert_eq!(context.image_paths, vec!["frame_001409.jpg", "frame_001410.jpg", "frame_001411.jpg"]);
+    }
+
+    #[test]
+    fn test_select_context_respects_budget() {
+        let manifest = long_manifest();
+
+        // Each line is "[MM:SS] line NNN" plus a newline
+        let budget = ContextBudget { max_chars: 60, max_frames: 0 };
+        let context = manifest.select_context(Some(2820.0), 300.0, budget);
+        assert_eq!(context.text, "[46:50] line 281\n[47:00] line 282\n[47:10] line 283");
+        assert!(context.image_paths.is_empty());
+
+        // Without a focus frames are spread over the whole video
+        let budget = ContextBudget { max_chars: 100, max_frames: 3 };
+        let context = manifest.select_context(None, 30.0, budget);
+        assert!(context.text.starts_with("[00:00] line 0\n"));
+        assert_eq!(context.image_paths, vec!["frame_000000.jpg", "frame_002699.jpg", "frame_005399.jpg"]);
+    }
+
+    #[test]
+    fn test_cleaned_transcript_keeps_raw() {
+        let mut manifest = long_manifest();
+        let cleaned = vec![TranscriptSegment { text: "All of it.".to_string(), start: 0.0, duration: 10800.0 }];
+
+        manifest.apply_cleaned_transcript(&cleaned);
+        manifest.apply_cleaned_transcript(&cleaned);
+        assert_eq!(manifest.transcript().len(), 1);
+        assert_eq!(manifest.raw_transcript.len(), 1080);
+        assert_eq!(manifest.frame_at(95.7), Some("frame_000047.jpg"));
+
+        manifest.replace_transcript(&manifest.raw_transcript.clone(), None);
+        assert!(manifest.raw_transcript.is_empty());
+        assert!(!serde_json::to_string(&manifest).unwrap().contains("raw_transcript"));
+    }
+
+    #[test]
+    fn test_frame_at() {
+        let manifest = long_manifest();
+        assert_eq!(manifest.frame_at(95.7), Some("frame_000047.jpg"));
+        assert_eq!(manifest.frame_at(1e9), Some("frame_005399.jpg"));
+
+        let mut no_frames = manifest.clone();
+        no_frames.items.retain(|item| matches!(item, ManifestItem::Text { .. }));
+        assert_eq!(no_frames.frame_at(95.7), None);
+    }
+
+    #[test]
+    fn test_malformed_manifests_are_rejected() {
+        let err = Manifest::parse("{\"version\": 2", "x").unwrap_err();
+        assert!(err.starts_with("not valid JSON"));
+
+        let err = Manifest::parse(r#"[{"t": 0, "kind": "video", "text": null, "path": null}]"#, "x").unwrap_err();
+        assert_eq!(err, "item 0 has unknown kind \"video\"");
+
+        let err = Manifest::parse(&V2.replace("\"version\": 2", "\"version\": 9"), "x").unwrap_err();
+        assert!(err.starts_with("unsupported version 9"));
+
+        let err = Manifest::parse(&V2.replace("\"t\": 1.0,", "\"t\": -1.0,"), "x").unwrap_err();
+        assert_eq!(err, "item 1 has invalid timestamp -1");
+
+        let err = Manifest::parse(r#"{"version": 2, "items": []}"#, "x").unwrap_err();
+        assert!(err.contains("missing field `video`"));
+    }
+}
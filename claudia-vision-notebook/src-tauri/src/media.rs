@@ -0,0 +1,389 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+use crate::progress::FfmpegProgress;
+
+/// Container and stream details reported by ffprobe
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MediaInfo {
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Average video frame rate
+    pub frame_rate: Option<f64>,
+    pub has_audio: bool,
+}
+
+/// Run ffprobe on a media file
+pub async fn probe_media(path: &Path) -> Result<MediaInfo, String> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("ffprobe failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    parse_ffprobe_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the JSON written by `ffprobe -print_format json -show_format -show_streams`
+pub fn parse_ffprobe_output(json: &str) -> Result<MediaInfo, String> {
+    let probe: JsonValue = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+
+    let format = &probe["format"];
+    let streams = probe["streams"].as_array().cloned().unwrap_or_default();
+    let video = streams.iter().find(|s| s["codec_type"] == "video");
+
+    // Tag names vary in case between containers (title vs TITLE)
+    let tag = |name: &str| {
+        format["tags"].as_object().and_then(|tags| {
+            tags.iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .and_then(|(_, value)| value.as_str())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+    };
+
+    Ok(MediaInfo {
+        duration: format["duration"].as_str().and_then(|d| d.parse::<f64>().ok()),
+        title: tag("title"),
+        artist: tag("artist").or_else(|| tag("author")),
+        width: video.and_then(|v| v["width"].as_u64()).map(|w| w as u32),
+        height: video.and_then(|v| v["height"].as_u64()).map(|h| h as u32),
+        frame_rate: video.and_then(|v| v["avg_frame_rate"].as_str()).and_then(parse_frame_rate),
+        has_audio: streams.iter().any(|s| s["codec_type"] == "audio"),
+    })
+}
+
+/// How frames are sampled from a video
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FrameSampling {
+    /// A fixed number of frames per second
+    Fps { fps: f64 },
+    /// The first frame plus one per scene change scoring above `threshold` (0-1)
+    SceneChange { threshold: f64 },
+}
+
+impl FrameSampling {
+    /// Video filter chain; `showinfo` logs each output frame's timestamp to stderr
+    fn filter(&self) -> String {
+        match self {
+            FrameSampling::Fps { fps } => format!("fps={},showinfo", fps),
+            FrameSampling::SceneChange { threshold } => {
+                format!("select='eq(n,0)+gt(scene,{})',showinfo", threshold)
+            }
+        }
+    }
+}
+
+/// A frame written to disk and its position in the video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampledFrame {
+    pub path: PathBuf,
+    /// Seconds from the start of the video
+    pub t: f64,
+}
+
+/// Sample frames from a video into `<output_dir>/frames`
+///
+/// `on_progress` receives the frames written so far, the number expected
+/// (known for fixed-rate sampling) and the share of `duration` processed.
+pub async fn extract_frames(
+    video_path: &Path,
+    output_dir: &Path,
+    sampling: FrameSampling,
+    duration: Option<f64>,
+    mut on_progress: impl FnMut(u64, Option<u64>, Option<f64>) + Send,
+) -> Result<Vec<SampledFrame>, String> {
+    let frames_dir = output_dir.join("frames");
+    std::fs::create_dir_all(&frames_dir)
+        .map_err(|e| format!("Failed to create frames directory: {}", e))?;
+    
+    // Frames from an earlier run would otherwise be paired with the new timestamps
+    for path in list_frames(&frames_dir)? {
+        let _ = std::fs::remove_file(path);
+    }
+    
+    let output_pattern = frames_dir.join("frame_%06d.jpg");
+    let expected = match (sampling, duration) {
+        (FrameSampling::Fps { fps }, Some(duration)) => Some((duration * fps).ceil() as u64),
+        _ => None,
+    };
+    
+    // Killed if the caller stops waiting, e.g. when a job is cancelled
+    let mut child = tokio::process::Command::new("ffmpeg")
+        .arg("-i")
+        .arg(video_path)
+        .arg("-vf")
+        .arg(sampling.filter())
+        .arg("-vsync")
+        .arg("vfr")
+        .args(["-progress", "pipe:1", "-nostats"])
+        .arg("-y")
+        .arg(&output_pattern)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    
+    let stdout = child.stdout.take().ok_or("Failed to read ffmpeg output")?;
+    let mut stderr = child.stderr.take().ok_or("Failed to read ffmpeg output")?;
+    
+    // stderr carries the showinfo log and must be drained alongside progress
+    let read_progress = async {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut progress = FfmpegProgress::default();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if progress.update(&line) {
+                on_progress(progress.frames, expected, progress.percent(duration));
+            }
+        }
+    };
+    let read_log = async {
+        let mut log = String::new();
+        let _ = stderr.read_to_string(&mut log).await;
+        log
+    };
+    let ((), log) = tokio::join!(read_progress, read_log);
+    
+    let status = child.wait().await
+        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed: {}", log));
+    }
+    
+    let frame_paths = list_frames(&frames_dir)?;
+    let times = parse_showinfo_times(&log);
+    
+    Ok(frame_paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let t = match (times.get(i), sampling) {
+                (Some(&t), _) => t,
+                // The fps filter emits frame i at i / fps
+                (None, FrameSampling::Fps { fps }) => i as f64 / fps,
+                (None, FrameSampling::SceneChange { .. }) => times.last().copied().unwrap_or(0.0),
+            };
+            SampledFrame { path, t }
+        })
+        .collect())
+}
+
+/// Convert the audio of a media file to the 16 kHz mono WAV speech models expect
+pub async fn extract_audio(input: &Path, output: &Path) -> Result<(), String> {
+    let result = tokio::process::Command::new("ffmpeg")
+        .arg("-i")
+        .arg(input)
+        .args(["-vn", "-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le", "-y"])
+        .arg(output)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+    
+    if !result.status.success() {
+        return Err(format!("ffmpeg failed to extract audio: {}", String::from_utf8_lossy(&result.stderr)));
+    }
+    Ok(())
+}
+
+/// Formats a clip can be cut to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipFormat {
+    Mp4,
+    /// Silent, 12 fps, 480 px wide
+    Gif,
+}
+
+impl ClipFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ClipFormat::Mp4 => "mp4",
+            ClipFormat::Gif => "gif",
+        }
+    }
+}
+
+/// Cut `start..end` seconds of a video into an MP4 or GIF
+///
+/// Clips are re-encoded so they begin exactly at `start` instead of the
+/// keyframe before it. `subtitles` is an SRT timed from the start of the clip;
+/// MP4 clips carry it as a subtitle track.
+pub async fn cut_clip(
+    input: &Path,
+    output: &Path,
+    start: f64,
+    end: f64,
+    format: ClipFormat,
+    subtitles: Option<&Path>,
+) -> Result<(), String> {
+    let result = tokio::process::Command::new("ffmpeg")
+        .args(clip_args(input, output, start, end, format, subtitles))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute ffmpeg: {}", e))?;
+
+    if !result.status.success() {
+        return Err(format!("ffmpeg failed to cut clip: {}", String::from_utf8_lossy(&result.stderr)));
+    }
+    Ok(())
+}
+
+fn clip_args(
+    input: &Path,
+    output: &Path,
+    start: f64,
+    end: f64,
+    format: ClipFormat,
+    subtitles: Option<&Path>,
+) -> Vec<std::ffi::OsString> {
+    let mut args: Vec<std::ffi::OsString> = vec![
+        // Seeking before the input is fast and, with re-encoding, still exact
+        "-ss".into(),
+        format!("{:.3}", start.max(0.0)).into(),
+        "-i".into(),
+        input.into(),
+    ];
+    let duration = format!("{:.3}", (end - start).max(0.0));
+
+    match (format, subtitles) {
+        (ClipFormat::Mp4, Some(subtitles)) => {
+            args.extend(["-i".into(), subtitles.into()]);
+            args.extend(["-t", duration.as_str(), "-map", "0:v:0", "-map", "0:a:0?", "-map", "1:0"].map(Into::into));
+            args.extend(["-c:s", "mov_text", "-metadata:s:s:0", "language=und"].map(Into::into));
+        }
+        (ClipFormat::Mp4, None) => {
+            args.extend(["-t", duration.as_str(), "-map", "0:v:0", "-map", "0:a:0?"].map(Into::into));
+        }
+        (ClipFormat::Gif, _) => {
+            args.extend(["-t", duration.as_str(), "-an", "-vf"].map(Into::into));
+            args.push("fps=12,scale=480:-1:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse".into());
+            args.extend(["-loop", "0"].map(Into::into));
+        }
+    }
+    if format == ClipFormat::Mp4 {
+        args.extend(
+            ["-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-c:a", "aac", "-movflags", "+faststart"]
+                .map(Into::into),
+        );
+    }
+
+    args.push("-y".into());
+    args.push(output.into());
+    args
+}
+
+fn list_frames(frames_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut frame_paths = Vec::new();
+    for entry in std::fs::read_dir(frames_dir)
+        .map_err(|e| format!("Failed to read frames directory: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        if entry.path().extension().and_then(|s| s.to_str()) == Some("jpg") {
+            frame_paths.push(entry.path());
+        }
+    }
+    
+    frame_paths.sort();
+    Ok(frame_paths)
+}
+
+/// Output frame timestamps from ffmpeg's `showinfo` log, in frame order
+fn parse_showinfo_times(stderr: &str) -> Vec<f64> {
+    let Ok(line) = Regex::new(r"\bn:\s*(\d+)\s+pts:\s*-?\d+\s+pts_time:\s*(-?[0-9.]+)") else {
+        return Vec::new();
+    };
+    
+    let mut frames: Vec<(u64, f64)> = stderr
+        .lines()
+        .filter(|l| l.contains("showinfo"))
+        .filter_map(|l| line.captures(l))
+        .filter_map(|caps| Some((caps[1].parse().ok()?, caps[2].parse().ok()?)))
+        .collect();
+    frames.sort_by_key(|(n, _)| *n);
+    frames.dedup_by_key(|(n, _)| *n);
+    
+    frames.into_iter().map(|(_, t)| t.max(0.0)).collect()
+}
+
+/// Parse ffprobe's rational frame rates such as `30000/1001`
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let (num, den) = rate.split_once('/').unwrap_or((rate, "1"));
+    let num = num.parse::<f64>().ok()?;
+    let den = den.parse::<f64>().ok()?;
+    if den == 0.0 || num == 0.0 {
+        None
+    } else {
+        Some(num / den)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ffprobe_fixture() {
+        let info = parse_ffprobe_output(include_str!("../tests/fixtures/media/ffprobe.json")).unwrap();
+
+        assert_eq!(info.duration, Some(5423.317));
+        assert_eq!(info.title.as_deref(), Some("Onboarding: Deploy Pipeline"));
+        assert_eq!(info.artist.as_deref(), Some("Platform Team"));
+        assert_eq!((info.width, info.height), (Some(1920), Some(1080)));
+        assert!((info.frame_rate.unwrap() - 29.97).abs() < 0.01);
+        assert!(info.has_audio);
+    }
+
+    #[test]
+    fn test_parse_showinfo_fixture() {
+        let times = parse_showinfo_times(include_str!("../tests/fixtures/media/showinfo.txt"));
+        assert_eq!(times, vec![0.0, 14.48, 73.2, 131.966]);
+    }
+
+    #[test]
+    fn test_clip_args() {
+        let args = |format, subtitles: Option<&Path>| -> Vec<String> {
+            clip_args(Path::new("talk.mp4"), Path::new("out"), 62.5, 75.0, format, subtitles)
+                .into_iter()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect()
+        };
+
+        let mp4 = args(ClipFormat::Mp4, Some(Path::new("clip.srt")));
+        assert_eq!(&mp4[..6], ["-ss", "62.500", "-i", "talk.mp4", "-i", "clip.srt"]);
+        assert!(mp4.windows(2).any(|pair| pair == ["-t", "12.500"]));
+        assert!(mp4.windows(2).any(|pair| pair == ["-c:s", "mov_text"]));
+        assert_eq!(mp4.last().map(String::as_str), Some("out"));
+
+        let gif = args(ClipFormat::Gif, Some(Path::new("clip.srt")));
+        assert!(!gif.contains(&"clip.srt".to_string()));
+        assert!(gif.contains(&"-an".to_string()) && !gif.contains(&"libx264".to_string()));
+    }
+
+    #[test]
+    fn test_frame_sampling_filter() {
+        assert_eq!(FrameSampling::Fps { fps: 0.5 }.filter(), "fps=0.5,showinfo");
+        assert_eq!(
+            FrameSampling::SceneChange { threshold: 0.3 }.filter(),
+            "select='eq(n,0)+gt(scene,0.3)',showinfo"
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use regex::Regex;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+
+/// Event carrying a `VideoProgress`; also emitted as `video-progress:<video_id>`
+pub const VIDEO_PROGRESS_EVENT: &str = "video-progress";
+
+/// Steps of processing a video, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    FetchingTranscript,
+    /// Speech-to-text for videos without captions
+    Transcribing,
+    Downloading,
+    ExtractingFrames,
+    WritingManifest,
+    Indexing,
+    Done,
+}
+
+/// Where processing of one video has got to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoProgress {
+    pub video_id: String,
+    pub stage: PipelineStage,
+    /// Completion of the current stage, 0-100, when it can be measured
+    pub percent: Option<f64>,
+    /// Items done in the current stage, e.g. frames extracted
+    pub current: Option<u64>,
+    /// Items expected in the current stage, when known in advance
+    pub total: Option<u64>,
+    /// Human-readable detail such as size, speed and ETA
+    pub detail: Option<String>,
+}
+
+/// Emits progress events for one video, dropping updates that change nothing visible
+pub struct ProgressReporter {
+    app: AppHandle,
+    video_id: String,
+    last: Option<(PipelineStage, Option<i64>, Option<u64>)>,
+}
+
+impl ProgressReporter {
+    pub fn new(app: &AppHandle, video_id: &str) -> Self {
+        Self {
+            app: app.clone(),
+            video_id: video_id.to_string(),
+            last: None,
+        }
+    }
+
+    /// Announce the start of a stage
+    pub fn stage(&mut self, stage: PipelineStage) {
+        self.report(stage, None, None, None, None);
+    }
+
+    /// Report a yt-dlp download update
+    pub fn download(&mut self, progress: &DownloadProgress) {
+        let detail = [
+            progress.total_size.as_ref().map(|size| format!("of {}", size)),
+            progress.speed.as_ref().map(|speed| format!("at {}", speed)),
+            progress.eta.as_ref().map(|eta| format!("ETA {}", eta)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+        self.report(
+            PipelineStage::Downloading,
+            Some(progress.percent),
+            None,
+            None,
+            (!detail.is_empty()).then_some(detail),
+        );
+    }
+
+    /// Report frames extracted so far
+    pub fn frames(&mut self, extracted: u64, expected: Option<u64>, percent: Option<f64>) {
+        self.report(PipelineStage::ExtractingFrames, percent, Some(extracted), expected, None);
+    }
+
+    fn report(
+        &mut self,
+        stage: PipelineStage,
+        percent: Option<f64>,
+        current: Option<u64>,
+        total: Option<u64>,
+        detail: Option<String>,
+    ) {
+        // Whole percents are enough for a progress bar
+        let key = (stage, percent.map(|p| p.floor() as i64), current);
+        if self.last == Some(key) {
+            return;
+        }
+        self.last = Some(key);
+
+        let progress = VideoProgress {
+            video_id: self.video_id.clone(),
+            stage,
+            percent,
+            current,
+            total,
+            detail,
+        };
+        let _ = self.app.emit(&format!("{}:{}", VIDEO_PROGRESS_EVENT, self.video_id), &progress);
+        let _ = self.app.emit(VIDEO_PROGRESS_EVENT, &progress);
+    }
+}
+
+/// One `[download]` progress line from yt-dlp run with `--newline`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadProgress {
+    pub percent: f64,
+    pub total_size: Option<String>,
+    pub speed: Option<String>,
+    pub eta: Option<String>,
+}
+
+/// Parse a yt-dlp progress line such as
+/// `[download]  42.0% of ~ 10.00MiB at  1.20MiB/s ETA 00:05`
+pub fn parse_ytdlp_progress(line: &str) -> Option<DownloadProgress> {
+    static PROGRESS: OnceLock<Regex> = OnceLock::new();
+    let re = PROGRESS.get_or_init(|| {
+        Regex::new(
+            r"^\[download\]\s+(\d+(?:\.\d+)?)%(?:\s+of\s+~?\s*(\S+))?(?:\s+at\s+(\S+))?(?:\s+ETA\s+(\S+))?",
+        )
+        .unwrap()
+    });
+    let caps = re.captures(line.trim())?;
+    // yt-dlp prints "Unknown" placeholders before the server reports sizes
+    let known = |index: usize| {
+        caps.get(index)
+            .map(|m| m.as_str().to_string())
+            .filter(|value| !value.starts_with("Unknown") && value != "N/A")
+    };
+
+    Some(DownloadProgress {
+        percent: caps[1].parse().ok()?,
+        total_size: known(2),
+        speed: known(3),
+        eta: known(4),
+    })
+}
+
+/// State built from ffmpeg's `-progress` output, one `key=value` per line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FfmpegProgress {
+    /// Frames written so far
+    pub frames: u64,
+    /// Seconds of input processed so far
+    pub out_time: Option<f64>,
+    /// Set by the final block
+    pub finished: bool,
+}
+
+impl FfmpegProgress {
+    /// Apply one line; true when it ends a block and the state is worth reporting
+    pub fn update(&mut self, line: &str) -> bool {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            return false;
+        };
+
+        match key {
+            "frame" => {
+                if let Ok(frames) = value.parse() {
+                    self.frames = frames;
+                }
+            }
+            // Both are microseconds despite the name of the second
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<i64>() {
+                    self.out_time = Some(micros.max(0) as f64 / 1_000_000.0);
+                }
+            }
+            "progress" => {
+                self.finished = value == "end";
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    /// Share of `duration` processed, 0-100
+    pub fn percent(&self, duration: Option<f64>) -> Option<f64> {
+        if self.finished {
+            return Some(100.0);
+        }
+        match (self.out_time, duration) {
+            (Some(t), Some(d)) if d > 0.0 => Some((t / d * 100.0).clamp(0.0, 100.0)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ytdlp_progress_fixture() {
+        let updates: Vec<DownloadProgress> = include_str!("../tests/fixtures/media/ytdlp_progress.txt")
+            .lines()
+            .filter_map(parse_ytdlp_progress)
+            .collect();
+
+        let percents: Vec<f64> = updates.iter().map(|p| p.percent).collect();
+        assert_eq!(percents, vec![0.0, 0.0, 12.5, 57.1, 100.0, 100.0]);
+        assert_eq!(updates[0].total_size.as_deref(), Some("48.20MiB"));
+        assert_eq!(updates[0].speed, None);
+        assert_eq!(updates[2].speed.as_deref(), Some("2.31MiB/s"));
+        assert_eq!(updates[2].eta.as_deref(), Some("00:18"));
+        // The summary line reports elapsed time, not an ETA
+        assert_eq!((updates[5].speed.as_ref(), updates[5].eta.as_ref()), (None, None));
+    }
+
+    #[test]
+    fn test_parse_ffmpeg_progress_fixture() {
+        let mut progress = FfmpegProgress::default();
+        let mut reports = Vec::new();
+        for line in include_str!("../tests/fixtures/media/ffmpeg_progress.txt").lines() {
+            if progress.update(line) {
+                reports.push((progress.frames, progress.percent(Some(120.0))));
+            }
+        }
+
+        assert_eq!(reports, vec![(0, None), (31, Some(50.0)), (61, Some(100.0))]);
+        assert!(progress.finished);
+        assert_eq!(progress.out_time, Some(120.0));
+    }
+}
@@ -0,0 +1,99 @@
+use serde_json::Value as JsonValue;
+
+/// Find the JSON array in model output that may wrap it in prose or code fences
+///
+/// Fenced blocks are searched first, then the whole text. The first `[` that
+/// starts a complete, valid JSON array wins.
+pub fn extract_json_array(output: &str) -> Result<&str, String> {
+    fenced_blocks(output)
+        .into_iter()
+        .chain(std::iter::once(output))
+        .find_map(first_json_array)
+        .ok_or_else(|| "No JSON array found in the response".to_string())
+}
+
+/// Contents of ``` fenced blocks, without the info string
+fn fenced_blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("```") {
+        let after = &rest[open + 3..];
+        // Skip the language tag on the opening line
+        let body_start = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+        let body = &after[body_start..];
+        match body.find("```") {
+            Some(close) => {
+                blocks.push(&body[..close]);
+                rest = &body[close + 3..];
+            }
+            None => break,
+        }
+    }
+    blocks
+}
+
+fn first_json_array(text: &str) -> Option<&str> {
+    text.match_indices('[').find_map(|(start, _)| {
+        let end = balanced_end(&text[start..])?;
+        let candidate = &text[start..start + end];
+        matches!(serde_json::from_str::<JsonValue>(candidate), Ok(JsonValue::Array(_)))
+            .then_some(candidate)
+    })
+}
+
+/// Byte length of the bracketed value at the start of `text`, skipping strings
+fn balanced_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, ch) in text.char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_plain_and_prefixed_arrays() {
+        assert_eq!(extract_json_array("[1, 2]").unwrap(), "[1, 2]");
+        assert_eq!(
+            extract_json_array("Here are the questions [as requested]:\n[{\"q\": \"a]b\"}]\nEnjoy!").unwrap(),
+            "[{\"q\": \"a]b\"}]"
+        );
+    }
+
+    #[test]
+    fn test_extract_fenced_array() {
+        let output = "Sure! [see below]\n```json\n[\n  {\"question\": \"Why?\"}\n]\n```\nLet me know.";
+        assert_eq!(extract_json_array(output).unwrap(), "[\n  {\"question\": \"Why?\"}\n]");
+    }
+
+    #[test]
+    fn test_missing_or_truncated_array() {
+        assert!(extract_json_array("I could not generate questions.").is_err());
+        assert!(extract_json_array("[{\"question\": \"cut off").is_err());
+    }
+}
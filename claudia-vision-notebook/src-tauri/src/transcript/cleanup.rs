@@ -0,0 +1,361 @@
+use serde::Deserialize;
+
+use crate::commands::vision_notebook::TranscriptSegment;
+
+/// How cleaned text is split into segments
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Segmentation {
+    Sentences,
+    Paragraphs,
+}
+
+/// Tuning for caption cleanup
+#[derive(Debug, Clone, Copy)]
+pub struct CleanupOptions {
+    pub segmentation: Segmentation,
+    /// Longest phrase checked for immediate repetition
+    pub max_ngram: usize,
+    /// Silence, in seconds, that ends a sentence when there is no punctuation
+    pub pause_gap: f64,
+    /// Sentences are cut at this many words even without a pause
+    pub max_sentence_words: usize,
+    /// Sentences per paragraph at most
+    pub paragraph_sentences: usize,
+    /// Silence, in seconds, that starts a new paragraph
+    pub paragraph_gap: f64,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        Self {
+            segmentation: Segmentation::Sentences,
+            max_ngram: 4,
+            pause_gap: 1.5,
+            max_sentence_words: 40,
+            paragraph_sentences: 5,
+            paragraph_gap: 3.0,
+        }
+    }
+}
+
+/// A word and the time it was spoken, interpolated within its caption
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedWord {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    /// Set where a caption marked a change of speaker (`>>`)
+    pub speaker_change: bool,
+}
+
+/// Words of a caption transcript with rolling overlaps merged and repeats collapsed
+pub fn caption_words(segments: &[TranscriptSegment], max_ngram: usize) -> Vec<TimedWord> {
+    collapse_repeats(merge_rolling_overlaps(segments), max_ngram)
+}
+
+/// Flatten captions into timed words, dropping text a caption repeats from the one before
+///
+/// Rolling auto-captions show each line twice: once as it is typed and again
+/// above the next line. The repeated words are skipped and the new words are
+/// spread evenly over the time left in the caption.
+fn merge_rolling_overlaps(segments: &[TranscriptSegment]) -> Vec<TimedWord> {
+    let mut words: Vec<TimedWord> = Vec::new();
+    let mut previous: Vec<String> = Vec::new();
+
+    for segment in segments {
+        let tokens: Vec<&str> = segment.text.split_whitespace().collect();
+        let normalized: Vec<String> = tokens.iter().map(|token| normalize(token)).collect();
+        if tokens.is_empty() {
+            continue;
+        }
+
+        let overlap = overlap_len(&previous, &normalized);
+        let new_tokens = &tokens[overlap..];
+        previous = normalized;
+        if new_tokens.is_empty() {
+            continue;
+        }
+
+        let end = segment.start + segment.duration.max(0.0);
+        let start = words.last().map_or(segment.start, |last| segment.start.max(last.end)).min(end);
+        let spoken = new_tokens.iter().filter(|token| !is_speaker_marker(token)).count();
+        let time_at = |index: usize| start + (end - start) * index as f64 / spoken as f64;
+
+        let mut speaker_change = false;
+        let mut index = 0;
+        for token in new_tokens {
+            if is_speaker_marker(token) {
+                speaker_change = true;
+                continue;
+            }
+            words.push(TimedWord {
+                text: token.to_string(),
+                start: time_at(index),
+                end: time_at(index + 1),
+                speaker_change,
+            });
+            speaker_change = false;
+            index += 1;
+        }
+    }
+
+    words
+}
+
+/// Words shared by the end of `previous` and the start of `next`
+///
+/// A single shared word only counts when one caption is a single word, so a
+/// genuinely repeated word across a caption boundary is kept.
+fn overlap_len(previous: &[String], next: &[String]) -> usize {
+    (1..=previous.len().min(next.len()))
+        .rev()
+        .find(|&k| {
+            previous[previous.len() - k..] == next[..k]
+                && (k >= 2 || previous.len() == 1 || next.len() == 1)
+        })
+        .unwrap_or(0)
+}
+
+/// Remove immediately repeated phrases of up to `max_ngram` words
+///
+/// Single words are only collapsed when said three or more times in a row,
+/// since "that that" and "had had" are ordinary English.
+fn collapse_repeats(mut words: Vec<TimedWord>, max_ngram: usize) -> Vec<TimedWord> {
+    for n in (1..=max_ngram.max(1)).rev() {
+        let min_copies = if n == 1 { 3 } else { 2 };
+        let mut i = 0;
+        while i + n <= words.len() {
+            let copies = repeated_copies(&words, i, n);
+            if copies >= min_copies {
+                // Keep the first copy, stretched over the time of the others
+                let last = i + copies * n;
+                let end = words[last - 1].end;
+                words.drain(i + n..last);
+                words[i + n - 1].end = end;
+            }
+            i += 1;
+        }
+    }
+    words
+}
+
+/// How many times the `n` words at `at` occur back to back
+fn repeated_copies(words: &[TimedWord], at: usize, n: usize) -> usize {
+    let mut copies = 1;
+    while at + (copies + 1) * n <= words.len() {
+        let next = at + copies * n;
+        let same = (0..n).all(|j| normalize(&words[at + j].text) == normalize(&words[next + j].text));
+        // A new speaker repeating a phrase is not a caption artifact
+        if !same || words[next].speaker_change {
+            break;
+        }
+        copies += 1;
+    }
+    copies
+}
+
+/// Group words into sentences, or sentences into paragraphs
+pub fn resegment(words: &[TimedWord], options: &CleanupOptions) -> Vec<TranscriptSegment> {
+    let sentences = split_sentences(words, options);
+    let groups = match options.segmentation {
+        Segmentation::Sentences => sentences,
+        Segmentation::Paragraphs => group_paragraphs(words, sentences, options),
+    };
+
+    groups
+        .into_iter()
+        .map(|range| {
+            let span = &words[range];
+            let start = span[0].start;
+            TranscriptSegment {
+                text: span.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+                start,
+                duration: (span[span.len() - 1].end - start).max(0.0),
+            }
+        })
+        .collect()
+}
+
+fn split_sentences(words: &[TimedWord], options: &CleanupOptions) -> Vec<std::ops::Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut begin = 0;
+
+    for (i, word) in words.iter().enumerate() {
+        let next = words.get(i + 1);
+        let boundary = match next {
+            None => true,
+            Some(next) => {
+                ends_sentence(&word.text)
+                    || next.speaker_change
+                    || next.start - word.end > options.pause_gap
+                    || i + 1 - begin >= options.max_sentence_words
+            }
+        };
+        if boundary {
+            sentences.push(begin..i + 1);
+            begin = i + 1;
+        }
+    }
+
+    sentences
+}
+
+fn group_paragraphs(
+    words: &[TimedWord],
+    sentences: Vec<std::ops::Range<usize>>,
+    options: &CleanupOptions,
+) -> Vec<std::ops::Range<usize>> {
+    let mut paragraphs: Vec<std::ops::Range<usize>> = Vec::new();
+    let mut count = 0;
+
+    for sentence in sentences {
+        let continues = paragraphs.last().is_some_and(|last| {
+            count < options.paragraph_sentences
+                && !words[sentence.start].speaker_change
+                && words[sentence.start].start - words[last.end - 1].end <= options.paragraph_gap
+        });
+        match paragraphs.last_mut() {
+            Some(last) if continues => {
+                last.end = sentence.end;
+                count += 1;
+            }
+            _ => {
+                paragraphs.push(sentence);
+                count = 1;
+            }
+        }
+    }
+
+    paragraphs
+}
+
+/// `>>` marks a new speaker and is not a word
+fn is_speaker_marker(token: &str) -> bool {
+    token.chars().all(|c| c == '>')
+}
+
+fn ends_sentence(word: &str) -> bool {
+    let word = word.trim_end_matches(['"', '\'', ')', ']', '\u{201d}']);
+    word.ends_with(['.', '?', '!', '\u{2026}'])
+}
+
+/// Lowercase letters and digits only, for comparing caption words
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Prompt asking the model to punctuate a run of caption words
+pub fn punctuation_prompt(words: &[TimedWord]) -> String {
+    let text = words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+    format!(
+        "The following is an automatic caption transcript without punctuation.
+        Add punctuation and capitalization. Do not add, remove, reorder or change any words.
+        Respond with only the punctuated text.
+
+        {}",
+        text
+    )
+}
+
+/// Copy the model's punctuated words onto `words`
+///
+/// Returns false and leaves `words` alone unless the response has exactly the
+/// same words in the same order.
+pub fn apply_punctuation(words: &mut [TimedWord], response: &str) -> bool {
+    let punctuated: Vec<&str> = response.split_whitespace().collect();
+    let matches = punctuated.len() == words.len()
+        && punctuated.iter().zip(words.iter()).all(|(new, old)| normalize(new) == normalize(&old.text));
+    if !matches {
+        return false;
+    }
+
+    for (word, new) in words.iter_mut().zip(punctuated) {
+        word.text = new.to_string();
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(cues: &[(f64, f64, &str)]) -> Vec<TranscriptSegment> {
+        cues.iter()
+            .map(|&(start, duration, text)| TranscriptSegment { text: text.to_string(), start, duration })
+            .collect()
+    }
+
+    fn texts(segments: &[TranscriptSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merges_rolling_caption_overlaps() {
+        let raw = segments(&[
+            (0.0, 3.0, "so today we're going"),
+            (1.5, 3.5, "so today we're going to talk about"),
+            (3.0, 3.0, "to talk about lifetimes"),
+            (6.0, 2.0, "lifetimes"),
+        ]);
+        let words = caption_words(&raw, 4);
+        let text: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(text, vec!["so", "today", "we're", "going", "to", "talk", "about", "lifetimes"]);
+
+        // New words fill the caption time left after the previous caption's words
+        assert_eq!((words[4].start, words[6].end), (3.0, 5.0));
+        assert!(words.windows(2).all(|pair| pair[0].end <= pair[1].start + 1e-9));
+    }
+
+    #[test]
+    fn test_collapses_repeated_ngrams() {
+        let raw = segments(&[(0.0, 12.0, "you know you know the the borrow checker I I I think that that works")]);
+        let words = caption_words(&raw, 4);
+        let text: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(text, vec!["you", "know", "the", "the", "borrow", "checker", "I", "think", "that", "that", "works"]);
+        assert_eq!(words.last().unwrap().end, 12.0);
+    }
+
+    #[test]
+    fn test_resegments_into_sentences_and_paragraphs() {
+        let raw = segments(&[
+            (0.0, 2.0, "Ownership is simple."),
+            (2.0, 2.0, "Borrowing is harder"),
+            // A long pause ends the unpunctuated sentence
+            (8.0, 2.0, "then lifetimes"),
+            (10.0, 1.0, ">> Any questions?"),
+        ]);
+
+        let words = caption_words(&raw, 4);
+        let sentences = resegment(&words, &CleanupOptions::default());
+        assert_eq!(texts(&sentences), vec![
+            "Ownership is simple.",
+            "Borrowing is harder",
+            "then lifetimes",
+            "Any questions?",
+        ]);
+        assert_eq!((sentences[1].start, sentences[1].duration), (2.0, 2.0));
+
+        let options = CleanupOptions { segmentation: Segmentation::Paragraphs, ..Default::default() };
+        let paragraphs = resegment(&words, &options);
+        assert_eq!(texts(&paragraphs), vec![
+            "Ownership is simple. Borrowing is harder",
+            "then lifetimes",
+            "Any questions?",
+        ]);
+        assert_eq!((paragraphs[0].start, paragraphs[0].duration), (0.0, 4.0));
+    }
+
+    #[test]
+    fn test_apply_punctuation_requires_same_words() {
+        let raw = segments(&[(0.0, 4.0, "so what is a lifetime it is a scope")]);
+        let mut words = caption_words(&raw, 4);
+
+        assert!(!apply_punctuation(&mut words, "So, what is a lifetime? It's a scope."));
+        assert_eq!(words[0].text, "so");
+
+        assert!(apply_punctuation(&mut words, "So, what is a lifetime? It is a scope."));
+        let sentences = resegment(&words, &CleanupOptions::default());
+        assert_eq!(texts(&sentences), vec!["So, what is a lifetime?", "It is a scope."]);
+    }
+}
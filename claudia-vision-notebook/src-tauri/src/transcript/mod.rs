@@ -0,0 +1,9 @@
+pub mod cleanup;
+pub mod playlist;
+pub mod provider;
+pub mod subtitles;
+pub mod timedtext;
+pub mod whisper;
+pub mod youtube;
+
+pub use provider::*;
@@ -0,0 +1,309 @@
+use async_trait::async_trait;
+use log::{debug, warn};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use std::sync::OnceLock;
+
+use super::youtube::{extract_json_object, http_client};
+
+const BROWSE_URL: &str = "https://www.youtube.com/youtubei/v1/browse?prettyPrint=false";
+
+/// Client version sent with continuation requests when the page has none
+const DEFAULT_CLIENT_VERSION: &str = "2.20240101.00.00";
+
+/// Upper bound on continuation requests for one collection
+const MAX_CONTINUATION_PAGES: usize = 50;
+
+/// Renderers that describe a single video in playlist and channel pages
+const VIDEO_RENDERERS: &[&str] = &["playlistVideoRenderer", "videoRenderer", "gridVideoRenderer"];
+
+/// A YouTube URL that stands for many videos
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum VideoCollection {
+    /// Playlist id (`PL...`, `OL...`, ...)
+    Playlist(String),
+    /// Channel path: `@handle`, `channel/UC...`, `c/name` or `user/name`
+    Channel(String),
+}
+
+impl VideoCollection {
+    /// Recognize playlist and channel URLs; single-video URLs return None
+    /// unless they carry a `list=` parameter
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        if !url.contains("youtube.com") {
+            return None;
+        }
+
+        static PLAYLIST: OnceLock<Regex> = OnceLock::new();
+        let playlist = PLAYLIST.get_or_init(|| Regex::new(r"[?&]list=([A-Za-z0-9_-]{10,})").unwrap());
+        if let Some(caps) = playlist.captures(url) {
+            return Some(Self::Playlist(caps[1].to_string()));
+        }
+
+        static CHANNEL: OnceLock<Regex> = OnceLock::new();
+        let channel = CHANNEL.get_or_init(|| {
+            Regex::new(r"youtube\.com/(@[^/?#\s]+|(?:channel|c|user)/[^/?#\s]+)").unwrap()
+        });
+        channel.captures(url).map(|caps| Self::Channel(caps[1].to_string()))
+    }
+
+    /// Page listing the collection's videos
+    pub fn page_url(&self) -> String {
+        match self {
+            Self::Playlist(id) => format!("https://www.youtube.com/playlist?list={}", id),
+            Self::Channel(path) => format!("https://www.youtube.com/{}/videos", path),
+        }
+    }
+}
+
+/// Videos in a playlist or channel, in page order
+#[derive(Debug, Clone, Serialize)]
+pub struct VideoList {
+    pub title: Option<String>,
+    pub video_ids: Vec<String>,
+}
+
+/// A source that expands a collection URL into video ids
+#[async_trait]
+pub trait VideoListProvider: Send + Sync {
+    async fn list_videos(&self, collection: &VideoCollection) -> Result<VideoList, String>;
+}
+
+/// Provider that reads the playlist or channel page and follows its continuations
+pub struct YouTubeBrowseProvider;
+
+#[async_trait]
+impl VideoListProvider for YouTubeBrowseProvider {
+    async fn list_videos(&self, collection: &VideoCollection) -> Result<VideoList, String> {
+        let client = http_client()?;
+        let html = client
+            .get(collection.page_url())
+            .query(&[("hl", "en")])
+            .header("Accept-Language", "en-US,en;q=0.9")
+            .header("Cookie", "CONSENT=YES+cb")
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch collection page: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read collection page: {}", e))?;
+
+        let page = parse_browse_page(&html)?;
+        let mut video_ids = page.video_ids;
+        let mut continuation = page.continuation;
+
+        for _ in 0..MAX_CONTINUATION_PAGES {
+            let Some(token) = continuation.take() else { break };
+            let body = json!({
+                "context": { "client": { "clientName": "WEB", "clientVersion": page.client_version, "hl": "en" } },
+                "continuation": token,
+            });
+            let response = async {
+                client.post(BROWSE_URL).json(&body).send().await?
+                    .error_for_status()?
+                    .json::<JsonValue>()
+                    .await
+            }.await;
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    // Keep what was listed so far rather than losing the batch
+                    warn!("Stopped listing videos after {}: {}", video_ids.len(), e);
+                    break;
+                }
+            };
+            let (ids, next) = parse_continuation(&response);
+            debug!("Continuation added {} videos", ids.len());
+            if ids.is_empty() {
+                break;
+            }
+            push_unique(&mut video_ids, ids);
+            continuation = next;
+        }
+
+        Ok(VideoList { title: page.title, video_ids })
+    }
+}
+
+/// First page of a playlist or channel listing
+#[derive(Debug)]
+pub struct BrowsePage {
+    pub title: Option<String>,
+    pub video_ids: Vec<String>,
+    /// Token for the next batch of videos, if there are more
+    pub continuation: Option<String>,
+    pub client_version: String,
+}
+
+/// Extract videos, title and continuation from playlist or channel page HTML
+pub fn parse_browse_page(html: &str) -> Result<BrowsePage, String> {
+    let data_json = extract_json_object(html, "ytInitialData")
+        .ok_or_else(|| "Could not find video list in page".to_string())?;
+    let data: JsonValue = serde_json::from_str(data_json)
+        .map_err(|e| format!("Failed to parse video list: {}", e))?;
+
+    if let Some(alert) = first_alert(&data) {
+        if data["contents"].is_null() {
+            return Err(format!("Collection is unavailable: {}", alert));
+        }
+    }
+
+    let title = data["metadata"]["playlistMetadataRenderer"]["title"]
+        .as_str()
+        .or_else(|| data["metadata"]["channelMetadataRenderer"]["title"].as_str())
+        .map(|title| title.to_string());
+
+    static CLIENT_VERSION: OnceLock<Regex> = OnceLock::new();
+    let client_version = CLIENT_VERSION
+        .get_or_init(|| Regex::new(r#""INNERTUBE_CLIENT_VERSION":"([^"]+)""#).unwrap())
+        .captures(html)
+        .map(|caps| caps[1].to_string())
+        .unwrap_or_else(|| DEFAULT_CLIENT_VERSION.to_string());
+
+    let mut video_ids = Vec::new();
+    let mut continuation = None;
+    collect_videos(&data["contents"], &mut video_ids, &mut continuation);
+
+    Ok(BrowsePage {
+        title,
+        video_ids: dedupe(video_ids),
+        continuation,
+        client_version,
+    })
+}
+
+/// Videos and the next token from a browse continuation response
+pub fn parse_continuation(response: &JsonValue) -> (Vec<String>, Option<String>) {
+    let mut video_ids = Vec::new();
+    let mut continuation = None;
+    collect_videos(&response["onResponseReceivedActions"], &mut video_ids, &mut continuation);
+    (dedupe(video_ids), continuation)
+}
+
+fn collect_videos(value: &JsonValue, video_ids: &mut Vec<String>, continuation: &mut Option<String>) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                if VIDEO_RENDERERS.contains(&key.as_str()) {
+                    // Deleted and private entries stay in playlists but cannot be played
+                    let playable = child["isPlayable"].as_bool().unwrap_or(true);
+                    if let (Some(id), true) = (child["videoId"].as_str(), playable) {
+                        video_ids.push(id.to_string());
+                    }
+                } else if key == "continuationCommand" {
+                    if let Some(token) = child["token"].as_str() {
+                        continuation.get_or_insert_with(|| token.to_string());
+                    }
+                } else {
+                    collect_videos(child, video_ids, continuation);
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                collect_videos(item, video_ids, continuation);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn first_alert(data: &JsonValue) -> Option<String> {
+    data["alerts"].as_array()?.iter().find_map(|alert| {
+        let renderer = &alert["alertRenderer"];
+        renderer["text"]["simpleText"]
+            .as_str()
+            .map(|s| s.to_string())
+            .or_else(|| {
+                renderer["text"]["runs"].as_array().map(|runs| {
+                    runs.iter().filter_map(|run| run["text"].as_str()).collect::<String>()
+                })
+            })
+    })
+}
+
+fn dedupe(ids: Vec<String>) -> Vec<String> {
+    let mut unique = Vec::new();
+    push_unique(&mut unique, ids);
+    unique
+}
+
+fn push_unique(target: &mut Vec<String>, ids: Vec<String>) {
+    for id in ids {
+        if !target.contains(&id) {
+            target.push(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYLIST_PAGE: &str = include_str!("../../tests/fixtures/youtube/playlist_page.html");
+    const CHANNEL_PAGE: &str = include_str!("../../tests/fixtures/youtube/channel_videos.html");
+    const CONTINUATION: &str = include_str!("../../tests/fixtures/youtube/browse_continuation.json");
+
+    #[test]
+    fn test_parse_collection_urls() {
+        assert_eq!(
+            VideoCollection::parse("https://www.youtube.com/playlist?list=PLabcdefghij123"),
+            Some(VideoCollection::Playlist("PLabcdefghij123".to_string()))
+        );
+        assert_eq!(
+            VideoCollection::parse("https://www.youtube.com/watch?v=abcDEF12345&list=PLabcdefghij123&index=2"),
+            Some(VideoCollection::Playlist("PLabcdefghij123".to_string()))
+        );
+        assert_eq!(
+            VideoCollection::parse("https://youtube.com/@crabacademy/videos"),
+            Some(VideoCollection::Channel("@crabacademy".to_string()))
+        );
+        assert_eq!(
+            VideoCollection::parse("https://www.youtube.com/channel/UCfixture123"),
+            Some(VideoCollection::Channel("channel/UCfixture123".to_string()))
+        );
+        assert_eq!(VideoCollection::parse("https://www.youtube.com/watch?v=abcDEF12345"), None);
+        assert_eq!(VideoCollection::parse("https://youtu.be/abcDEF12345"), None);
+        assert_eq!(
+            VideoCollection::Channel("@crabacademy".to_string()).page_url(),
+            "https://www.youtube.com/@crabacademy/videos"
+        );
+    }
+
+    #[test]
+    fn test_parse_playlist_page_fixture() {
+        let page = parse_browse_page(PLAYLIST_PAGE).unwrap();
+        assert_eq!(page.title.as_deref(), Some("Rust Course"));
+        // The deleted entry is skipped and the repeated one listed once
+        assert_eq!(page.video_ids, vec!["abcDEF12345", "ghiJKL67890", "mnoPQR13579"]);
+        assert_eq!(page.continuation.as_deref(), Some("4qmFsgKXARIkVkx"));
+        assert_eq!(page.client_version, "2.20240611.01.00");
+
+        let continuation: JsonValue = serde_json::from_str(CONTINUATION).unwrap();
+        let (ids, next) = parse_continuation(&continuation);
+        assert_eq!(ids, vec!["stuVWX24680", "yzaBCD11223"]);
+        assert_eq!(next, None);
+    }
+
+    #[test]
+    fn test_parse_channel_page_fixture() {
+        let page = parse_browse_page(CHANNEL_PAGE).unwrap();
+        assert_eq!(page.title.as_deref(), Some("Crab Academy"));
+        assert_eq!(page.video_ids, vec!["chaNNEL0001", "chaNNEL0002"]);
+        assert_eq!(page.continuation, None);
+    }
+
+    #[test]
+    fn test_unavailable_playlist() {
+        let html = r#"<script>var ytInitialData = {"alerts":[{"alertRenderer":{"type":"ERROR","text":{"runs":[{"text":"The playlist does not exist."}]}}}]};</script>"#;
+        assert_eq!(
+            parse_browse_page(html).unwrap_err(),
+            "Collection is unavailable: The playlist does not exist."
+        );
+        assert!(parse_browse_page("<html></html>").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use async_trait::async_trait;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::commands::vision_notebook::{TranscriptSegment, VideoInfo};
+use crate::manifest::Chapter;
+use super::whisper::{SharedWhisperConfig, WhisperProvider};
+
+/// One language track of a transcript as returned by a provider
+#[derive(Debug)]
+pub struct TranscriptTrack {
+    pub segments: Vec<TranscriptSegment>,
+    /// BCP-47 language code, when the provider knows it
+    pub language: Option<String>,
+    /// True for machine-generated captions
+    pub is_auto_generated: bool,
+    /// True when transcribed locally from the audio rather than published captions
+    pub is_machine_transcribed: bool,
+    /// Creator chapters, when the provider knows them
+    pub chapters: Vec<Chapter>,
+}
+
+/// A transcript together with where it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchedTranscript {
+    pub video_info: VideoInfo,
+    pub segments: Vec<TranscriptSegment>,
+    pub language: Option<String>,
+    pub is_auto_generated: bool,
+    #[serde(default)]
+    pub is_machine_transcribed: bool,
+    pub chapters: Vec<Chapter>,
+    /// Name of the provider that produced the transcript
+    pub provider: String,
+}
+
+/// A source of transcripts for a video id
+#[async_trait]
+pub trait TranscriptProvider: Send + Sync {
+    /// Stable identifier, stored on `transcript_history.provider`
+    fn name(&self) -> &'static str;
+
+    /// Fetch video metadata and timed segments for a video
+    ///
+    /// With a `language`, providers must return that language or fail;
+    /// without one they pick their best track.
+    async fn fetch(&self, video_id: &str, language: Option<&str>) -> Result<(VideoInfo, TranscriptTrack), String>;
+}
+
+/// Ordered set of transcript providers tried until one succeeds
+pub struct TranscriptProviderRegistry {
+    providers: Vec<Box<dyn TranscriptProvider>>,
+}
+
+impl TranscriptProviderRegistry {
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Add a provider; providers are tried in registration order by default
+    pub fn register(&mut self, provider: Box<dyn TranscriptProvider>) {
+        self.providers.retain(|p| p.name() != provider.name());
+        self.providers.push(provider);
+    }
+
+    /// Names of all registered providers in registration order
+    pub fn names(&self) -> Vec<String> {
+        self.providers.iter().map(|p| p.name().to_string()).collect()
+    }
+
+    /// Providers in the configured order; unlisted providers follow in registration order
+    pub fn ordered(&self, order: &[String]) -> Vec<&dyn TranscriptProvider> {
+        let mut ordered: Vec<&dyn TranscriptProvider> = order
+            .iter()
+            .filter_map(|name| self.providers.iter().find(|p| p.name() == name))
+            .map(|p| p.as_ref())
+            .collect();
+
+        for provider in &self.providers {
+            if !order.iter().any(|name| name == provider.name()) {
+                ordered.push(provider.as_ref());
+            }
+        }
+
+        ordered
+    }
+
+    /// Try each provider in order and return the first transcript with segments
+    pub async fn fetch(
+        &self,
+        video_id: &str,
+        language: Option<&str>,
+        order: &[String],
+    ) -> Result<FetchedTranscript, String> {
+        let mut errors = Vec::new();
+
+        for provider in self.ordered(order) {
+            match provider.fetch(video_id, language).await {
+                Ok((video_info, track)) if !track.segments.is_empty() => {
+                    info!("Fetched transcript for {} via {}", video_id, provider.name());
+                    return Ok(FetchedTranscript {
+                        video_info,
+                        segments: track.segments,
+                        language: track.language,
+                        is_auto_generated: track.is_auto_generated,
+                        is_machine_transcribed: track.is_machine_transcribed,
+                        chapters: track.chapters,
+                        provider: provider.name().to_string(),
+                    });
+                }
+                Ok(_) => errors.push(format!("{}: transcript was empty", provider.name())),
+                Err(e) => {
+                    warn!("Transcript provider {} failed for {}: {}", provider.name(), video_id, e);
+                    errors.push(format!("{}: {}", provider.name(), e));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Err("No transcript providers are registered".to_string())
+        } else {
+            Err(format!("All transcript providers failed: {}", errors.join("; ")))
+        }
+    }
+}
+
+impl TranscriptProviderRegistry {
+    /// Published captions first, then local transcription as the fallback
+    pub fn with_defaults(whisper: SharedWhisperConfig) -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(super::youtube::YouTubeCaptionProvider));
+        registry.register(Box::new(WhisperProvider::new(whisper)));
+        registry
+    }
+}
+
+impl Default for TranscriptProviderRegistry {
+    fn default() -> Self {
+        Self::with_defaults(SharedWhisperConfig::default())
+    }
+}
+
+/// Global transcript provider registry state
+pub struct TranscriptProviderState(pub Arc<TranscriptProviderRegistry>);
+
+impl TranscriptProviderState {
+    pub fn new(whisper: SharedWhisperConfig) -> Self {
+        Self(Arc::new(TranscriptProviderRegistry::with_defaults(whisper)))
+    }
+}
+
+impl Default for TranscriptProviderState {
+    fn default() -> Self {
+        Self(Arc::new(TranscriptProviderRegistry::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubProvider {
+        name: &'static str,
+        result: Result<usize, &'static str>,
+    }
+
+    #[async_trait]
+    impl TranscriptProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn fetch(&self, video_id: &str, language: Option<&str>) -> Result<(VideoInfo, TranscriptTrack), String> {
+            let count = self.result.map_err(|e| e.to_string())?;
+            let info = VideoInfo {
+                video_id: video_id.to_string(),
+                title: self.name.to_string(),
+                channel: "Stub".to_string(),
+                duration: None,
+                thumbnail_url: None,
+            };
+            let segments = (0..count)
+                .map(|i| TranscriptSegment {
+                    text: format!("segment {}", i),
+                    start: i as f64,
+                    duration: 1.0,
+                })
+                .collect();
+            let track = TranscriptTrack {
+                segments,
+                language: language.map(|l| l.to_string()),
+                is_auto_generated: false,
+                is_machine_transcribed: false,
+                chapters: Vec::new(),
+            };
+            Ok((info, track))
+        }
+    }
+
+    fn registry() -> TranscriptProviderRegistry {
+        let mut registry = TranscriptProviderRegistry::new();
+        registry.register(Box::new(StubProvider { name: "failing", result: Err("no captions") }));
+        registry.register(Box::new(StubProvider { name: "empty", result: Ok(0) }));
+        registry.register(Box::new(StubProvider { name: "working", result: Ok(2) }));
+        registry.register(Box::new(StubProvider { name: "backup", result: Ok(1) }));
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_registry_falls_back_in_order() {
+        let fetched = registry().fetch("vid", None, &[]).await.unwrap();
+        assert_eq!(fetched.provider, "working");
+        assert_eq!(fetched.segments.len(), 2);
+
+        let fetched = registry().fetch("vid", Some("de"), &["backup".to_string()]).await.unwrap();
+        assert_eq!(fetched.provider, "backup");
+        assert_eq!(fetched.language.as_deref(), Some("de"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_reports_every_failure() {
+        let order = vec!["failing".to_string(), "empty".to_string()];
+        let mut registry = TranscriptProviderRegistry::new();
+        registry.register(Box::new(StubProvider { name: "failing", result: Err("no captions") }));
+        registry.register(Box::new(StubProvider { name: "empty", result: Ok(0) }));
+
+        let err = registry.fetch("vid", None, &order).await.unwrap_err();
+        assert!(err.contains("failing: no captions"));
+        assert!(err.contains("empty: transcript was empty"));
+    }
+
+    #[test]
+    fn test_ordered_appends_unlisted_providers() {
+        let registry = registry();
+        let order = vec!["backup".to_string(), "unknown".to_string(), "failing".to_string()];
+        let names: Vec<&str> = registry.ordered(&order).iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["backup", "failing", "empty", "working"]);
+    }
+}
@@ -0,0 +1,466 @@
+use serde::{Deserialize, Serialize};
+
+use super::timedtext::{decode_entities, strip_tags};
+use crate::commands::vision_notebook::TranscriptSegment;
+
+/// Subtitle formats a transcript can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ttml,
+    /// Timestamped plain text
+    Txt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+            SubtitleFormat::Ttml => "ttml",
+            SubtitleFormat::Txt => "txt",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "vtt" => Some(SubtitleFormat::Vtt),
+            "ttml" | "dfxp" => Some(SubtitleFormat::Ttml),
+            "txt" => Some(SubtitleFormat::Txt),
+            _ => None,
+        }
+    }
+}
+
+/// Rules for turning transcript segments into subtitle cues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CueOptions {
+    /// Maximum characters per subtitle line
+    pub max_line_length: usize,
+    /// Maximum lines per cue; longer segments are split into several cues
+    pub max_lines: usize,
+    /// Gap in seconds kept between a cue and the next one when clipping overlaps
+    pub min_gap: f64,
+    /// Display time in seconds for segments without a duration
+    pub default_duration: f64,
+}
+
+impl Default for CueOptions {
+    fn default() -> Self {
+        Self {
+            max_line_length: 42,
+            max_lines: 2,
+            min_gap: 0.0,
+            default_duration: 2.0,
+        }
+    }
+}
+
+/// A single timed subtitle cue
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub lines: Vec<String>,
+}
+
+/// Render transcript segments in the given subtitle format
+pub fn render_subtitles(segments: &[TranscriptSegment], format: SubtitleFormat, options: &CueOptions) -> String {
+    match format {
+        SubtitleFormat::Srt => render_srt(&build_cues(segments, options)),
+        SubtitleFormat::Vtt => render_vtt(&build_cues(segments, options)),
+        SubtitleFormat::Ttml => render_ttml(&build_cues(segments, options)),
+        SubtitleFormat::Txt => render_text(segments),
+    }
+}
+
+/// Parse an SRT or WebVTT file into transcript segments
+///
+/// WebVTT is detected from its header, so a mislabelled file still parses.
+pub fn parse_subtitles(content: &str) -> Result<Vec<TranscriptSegment>, String> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+    let is_vtt = content.trim_start().starts_with("WEBVTT");
+
+    let mut segments = Vec::new();
+    for block in content.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+
+        // The timing line follows an optional numeric index (SRT) or cue identifier (VTT)
+        let timing_index = match lines.iter().take(2).position(|l| l.contains("-->")) {
+            Some(index) => index,
+            None => continue,
+        };
+        let (start, end) = parse_timing_line(lines[timing_index])
+            .ok_or_else(|| format!("Invalid subtitle timing: {}", lines[timing_index]))?;
+
+        let text = lines[timing_index + 1..]
+            .iter()
+            .map(|line| clean_cue_text(line, is_vtt))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            continue;
+        }
+
+        segments.push(TranscriptSegment {
+            text,
+            start,
+            duration: (end - start).max(0.0),
+        });
+    }
+
+    if segments.is_empty() {
+        return Err("No subtitle cues found".to_string());
+    }
+
+    segments.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(segments)
+}
+
+fn parse_timing_line(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    // VTT cue settings (align:start position:10%) follow the end time
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm`
+pub(crate) fn parse_timestamp(value: &str) -> Option<f64> {
+    let value = value.replace(',', ".");
+    let parts: Vec<&str> = value.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        _ => return None,
+    };
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn clean_cue_text(line: &str, is_vtt: bool) -> String {
+    // SSA-style positioning such as {\an8} is common in SRT files
+    let mut text = line.to_string();
+    while let (Some(open), Some(close)) = (text.find("{\\"), text.find('}')) {
+        if close < open {
+            break;
+        }
+        text.replace_range(open..=close, "");
+    }
+
+    let text = strip_tags(&text);
+    let text = if is_vtt { decode_entities(&text) } else { text };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Segments heard between `start` and `end`, retimed to begin at zero
+///
+/// Segments running over either edge are trimmed to it.
+pub fn slice_segments(segments: &[TranscriptSegment], start: f64, end: f64) -> Vec<TranscriptSegment> {
+    segments
+        .iter()
+        .filter(|s| s.start < end && (s.start + s.duration > start || s.start >= start))
+        .map(|s| {
+            let from = s.start.max(start);
+            let to = (s.start + s.duration).min(end).max(from);
+            TranscriptSegment {
+                text: s.text.clone(),
+                start: from - start,
+                duration: to - from,
+            }
+        })
+        .collect()
+}
+
+/// Split segments into cues that fit the line limits and never overlap
+pub fn build_cues(segments: &[TranscriptSegment], options: &CueOptions) -> Vec<Cue> {
+    let max_line_length = options.max_line_length.max(1);
+    let max_lines = options.max_lines.max(1);
+
+    let mut sorted: Vec<&TranscriptSegment> = segments.iter().filter(|s| !s.text.trim().is_empty()).collect();
+    sorted.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Segments starting together are shown as one cue instead of two on top of each other
+    let mut ordered: Vec<TranscriptSegment> = Vec::new();
+    for segment in sorted {
+        match ordered.last_mut() {
+            Some(last) if last.start == segment.start => {
+                last.text = format!("{} {}", last.text.trim(), segment.text.trim());
+                last.duration = last.duration.max(segment.duration);
+            }
+            _ => ordered.push(TranscriptSegment {
+                text: segment.text.clone(),
+                start: segment.start,
+                duration: segment.duration,
+            }),
+        }
+    }
+
+    let mut cues: Vec<Cue> = Vec::new();
+    for (i, segment) in ordered.iter().enumerate() {
+        let duration = if segment.duration > 0.0 { segment.duration } else { options.default_duration };
+        let mut end = segment.start + duration;
+
+        // Rolling captions overlap the next segment; cut them off where it starts
+        if let Some(next) = ordered.get(i + 1) {
+            end = end.min(next.start - options.min_gap).max(segment.start + 0.001);
+        }
+
+        let lines = wrap_text(&segment.text, max_line_length);
+        let groups: Vec<&[String]> = lines.chunks(max_lines).collect();
+        let total_chars: usize = lines.iter().map(|l| l.chars().count()).sum::<usize>().max(1);
+
+        // Share the segment's time between its cues in proportion to their text
+        let mut cue_start = segment.start;
+        for (j, group) in groups.iter().enumerate() {
+            let chars: usize = group.iter().map(|l| l.chars().count()).sum();
+            let cue_end = if j + 1 == groups.len() {
+                end
+            } else {
+                cue_start + (end - segment.start) * chars as f64 / total_chars as f64
+            };
+            cues.push(Cue {
+                start: cue_start,
+                end: cue_end,
+                lines: group.to_vec(),
+            });
+            cue_start = cue_end;
+        }
+    }
+
+    cues
+}
+
+/// Greedy word wrap; words longer than a line are kept whole
+fn wrap_text(text: &str, max_line_length: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max_line_length {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+fn render_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start, ','),
+            format_timestamp(cue.end, ','),
+            cue.lines.join("\n")
+        ));
+    }
+    out
+}
+
+fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start, '.'),
+            format_timestamp(cue.end, '.'),
+            cue.lines.iter().map(|l| escape_markup(l)).collect::<Vec<_>>().join("\n")
+        ));
+    }
+    out
+}
+
+fn render_ttml(cues: &[Cue]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\">\n  <body>\n    <div>\n",
+    );
+    for cue in cues {
+        out.push_str(&format!(
+            "      <p begin=\"{}\" end=\"{}\">{}</p>\n",
+            format_timestamp(cue.start, '.'),
+            format_timestamp(cue.end, '.'),
+            cue.lines.iter().map(|l| escape_markup(l)).collect::<Vec<_>>().join("<br/>")
+        ));
+    }
+    out.push_str("    </div>\n  </body>\n</tt>\n");
+    out
+}
+
+fn render_text(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .map(|s| format!("[{}] {}\n", format_clock(s.start), s.text.trim()))
+        .collect()
+}
+
+/// `HH:MM:SS` followed by the separator and milliseconds
+fn format_timestamp(seconds: f64, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        separator,
+        total_ms % 1000
+    )
+}
+
+/// `MM:SS`, or `H:MM:SS` past the first hour
+pub fn format_clock(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    if total >= 3600 {
+        format!("{}:{:02}:{:02}", total / 3600, (total / 60) % 60, total % 60)
+    } else {
+        format!("{:02}:{:02}", total / 60, total % 60)
+    }
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, start: f64, duration: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start,
+            duration,
+        }
+    }
+
+    const SRT: &str = include_str!("../../tests/fixtures/subtitles/talk.srt");
+    const VTT: &str = include_str!("../../tests/fixtures/subtitles/talk.vtt");
+
+    #[test]
+    fn test_parse_srt_fixture() {
+        let segments = parse_subtitles(SRT).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "Good morning, everyone.");
+        assert_eq!(segments[0].start, 1.0);
+        assert_eq!(segments[0].duration, 2.5);
+        assert_eq!(segments[1].text, "Today: memory safety without a garbage collector.");
+        assert_eq!(segments[2].start, 3723.04);
+    }
+
+    #[test]
+    fn test_parse_vtt_fixture() {
+        let segments = parse_subtitles(VTT).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "Good morning, everyone.");
+        assert_eq!(segments[1].text, "Today: memory safety & speed.");
+        assert_eq!(segments[1].start, 3.75);
+        assert_eq!(segments[2].text, "Let's begin.");
+        assert_eq!(segments[2].start, 62.0);
+    }
+
+    #[test]
+    fn test_parse_subtitles_round_trips_srt_export() {
+        let segments = vec![segment("one", 0.5, 1.0), segment("two", 2.0, 1.25)];
+        let srt = render_subtitles(&segments, SubtitleFormat::Srt, &CueOptions::default());
+        let parsed = parse_subtitles(&srt).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[1].text.as_str(), parsed[1].start, parsed[1].duration), ("two", 2.0, 1.25));
+    }
+
+    #[test]
+    fn test_slice_segments_retimes_to_clip() {
+        let segments = vec![
+            segment("before", 0.0, 9.0),
+            segment("edge", 8.0, 4.0),
+            segment("inside", 12.0, 3.0),
+            segment("after", 20.0, 2.0),
+        ];
+        let slice = slice_segments(&segments, 10.0, 20.0);
+        let texts: Vec<&str> = slice.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["edge", "inside"]);
+        assert_eq!((slice[0].start, slice[0].duration), (0.0, 2.0));
+        assert_eq!((slice[1].start, slice[1].duration), (2.0, 3.0));
+    }
+
+    #[test]
+    fn test_build_cues_clips_overlaps() {
+        let segments = vec![
+            segment("first line", 0.0, 4.0),
+            segment("second line", 2.5, 4.0),
+            segment("no duration", 8.0, 0.0),
+        ];
+        let cues = build_cues(&segments, &CueOptions::default());
+
+        assert_eq!(cues.len(), 3);
+        assert_eq!((cues[0].start, cues[0].end), (0.0, 2.5));
+        assert_eq!((cues[1].start, cues[1].end), (2.5, 6.5));
+        assert_eq!((cues[2].start, cues[2].end), (8.0, 10.0));
+    }
+
+    #[test]
+    fn test_build_cues_merges_equal_starts() {
+        let segments = vec![
+            segment("speaker one", 5.0, 2.0),
+            segment("first line", 0.0, 4.0),
+            segment("speaker two", 5.0, 3.0),
+        ];
+        let cues = build_cues(&segments, &CueOptions::default());
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].start, cues[0].end), (0.0, 4.0));
+        assert_eq!(cues[1].lines.join(" "), "speaker one speaker two");
+        assert_eq!((cues[1].start, cues[1].end), (5.0, 8.0));
+    }
+
+    #[test]
+    fn test_build_cues_splits_long_segments() {
+        let options = CueOptions {
+            max_line_length: 10,
+            max_lines: 1,
+            ..CueOptions::default()
+        };
+        let cues = build_cues(&[segment("aaaa bbbb cccc dddd", 10.0, 4.0)], &options);
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].lines, vec!["aaaa bbbb"]);
+        assert_eq!(cues[1].lines, vec!["cccc dddd"]);
+        assert_eq!((cues[0].start, cues[0].end), (10.0, 12.0));
+        assert_eq!((cues[1].start, cues[1].end), (12.0, 14.0));
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let segments = vec![segment("hello <world> & more", 3661.5, 2.0)];
+        let options = CueOptions::default();
+
+        assert_eq!(
+            render_subtitles(&segments, SubtitleFormat::Srt, &options),
+            "1\n01:01:01,500 --> 01:01:03,500\nhello <world> & more\n\n"
+        );
+        assert_eq!(
+            render_subtitles(&segments, SubtitleFormat::Vtt, &options),
+            "WEBVTT\n\n01:01:01.500 --> 01:01:03.500\nhello &lt;world&gt; &amp; more\n\n"
+        );
+        assert!(render_subtitles(&segments, SubtitleFormat::Ttml, &options)
+            .contains("<p begin=\"01:01:01.500\" end=\"01:01:03.500\">hello &lt;world&gt; &amp; more</p>"));
+        assert_eq!(
+            render_subtitles(&segments, SubtitleFormat::Txt, &options),
+            "[1:01:01] hello <world> & more\n"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::commands::vision_notebook::TranscriptSegment;
+
+/// Caption formats served by YouTube's timedtext endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimedTextFormat {
+    /// JSON events (`fmt=json3`)
+    Json3,
+    /// Timed text XML version 3 (`fmt=srv3`)
+    Srv3,
+    /// Legacy `<transcript><text>` XML (no `fmt` parameter)
+    Xml,
+}
+
+impl TimedTextFormat {
+    /// Value of the `fmt` query parameter, if the format needs one
+    pub fn query_value(&self) -> Option<&'static str> {
+        match self {
+            TimedTextFormat::Json3 => Some("json3"),
+            TimedTextFormat::Srv3 => Some("srv3"),
+            TimedTextFormat::Xml => None,
+        }
+    }
+
+    /// Guess the format of a timedtext response body
+    pub fn detect(body: &str) -> Option<Self> {
+        let trimmed = body.trim_start();
+        if trimmed.starts_with('{') {
+            Some(TimedTextFormat::Json3)
+        } else if trimmed.contains("<timedtext") {
+            Some(TimedTextFormat::Srv3)
+        } else if trimmed.contains("<transcript") {
+            Some(TimedTextFormat::Xml)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse a timedtext response body, detecting its format
+pub fn parse_timedtext(body: &str) -> Result<Vec<TranscriptSegment>, String> {
+    match TimedTextFormat::detect(body) {
+        Some(format) => parse_timedtext_as(body, format),
+        None => Err("Unrecognized caption format".to_string()),
+    }
+}
+
+fn parse_timedtext_as(body: &str, format: TimedTextFormat) -> Result<Vec<TranscriptSegment>, String> {
+    match format {
+        TimedTextFormat::Json3 => parse_json3(body),
+        TimedTextFormat::Srv3 => parse_srv3(body),
+        TimedTextFormat::Xml => parse_xml(body),
+    }
+}
+
+#[derive(Deserialize)]
+struct Json3Document {
+    #[serde(default)]
+    events: Vec<Json3Event>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Event {
+    #[serde(default)]
+    t_start_ms: u64,
+    d_duration_ms: Option<u64>,
+    segs: Option<Vec<Json3Seg>>,
+}
+
+#[derive(Deserialize)]
+struct Json3Seg {
+    #[serde(default)]
+    utf8: String,
+}
+
+fn parse_json3(body: &str) -> Result<Vec<TranscriptSegment>, String> {
+    let document: Json3Document = serde_json::from_str(body)
+        .map_err(|e| format!("Failed to parse json3 captions: {}", e))?;
+
+    let segments = document.events.into_iter()
+        .filter_map(|event| {
+            let text: String = event.segs?.into_iter().map(|seg| seg.utf8).collect();
+            Some(TranscriptSegment {
+                text: normalize_whitespace(&text),
+                start: event.t_start_ms as f64 / 1000.0,
+                duration: event.d_duration_ms.unwrap_or(0) as f64 / 1000.0,
+            })
+        })
+        .filter(|segment| !segment.text.is_empty())
+        .collect();
+
+    Ok(segments)
+}
+
+fn parse_srv3(body: &str) -> Result<Vec<TranscriptSegment>, String> {
+    // Empty paragraphs are written self-closing and would otherwise swallow the next one
+    let self_closing = Regex::new(r"<p\b[^>]*/>").map_err(|e| format!("Regex error: {}", e))?;
+    let paragraph = Regex::new(r"(?s)<p\b([^>]*)>(.*?)</p>").map_err(|e| format!("Regex error: {}", e))?;
+    let body = self_closing.replace_all(body, "");
+
+    let mut segments = Vec::new();
+    for caps in paragraph.captures_iter(&body) {
+        let attrs = &caps[1];
+        let start_ms = attribute(attrs, "t").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        let duration_ms = attribute(attrs, "d").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        let text = normalize_whitespace(&decode_entities(&strip_tags(&caps[2])));
+        if text.is_empty() {
+            continue;
+        }
+        segments.push(TranscriptSegment {
+            text,
+            start: start_ms / 1000.0,
+            duration: duration_ms / 1000.0,
+        });
+    }
+
+    Ok(segments)
+}
+
+fn parse_xml(body: &str) -> Result<Vec<TranscriptSegment>, String> {
+    let element = Regex::new(r"(?s)<text\b([^>]*?)(?:/>|>(.*?)</text>)")
+        .map_err(|e| format!("Regex error: {}", e))?;
+
+    let mut segments = Vec::new();
+    for caps in element.captures_iter(body) {
+        let attrs = &caps[1];
+        let start = attribute(attrs, "start").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        let duration = attribute(attrs, "dur").and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+        // The legacy format HTML-escapes the caption text before XML-escaping it
+        let raw = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        let text = normalize_whitespace(&strip_tags(&decode_entities(&decode_entities(raw))));
+        if text.is_empty() {
+            continue;
+        }
+        segments.push(TranscriptSegment { text, start, duration });
+    }
+
+    Ok(segments)
+}
+
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{}=\"", name);
+    let mut offset = 0;
+    while let Some(pos) = attrs[offset..].find(&needle) {
+        let start = offset + pos;
+        let at_boundary = attrs[..start].chars().next_back().is_none_or(char::is_whitespace);
+        let value_start = start + needle.len();
+        if at_boundary {
+            let value_end = attrs[value_start..].find('"')? + value_start;
+            return Some(&attrs[value_start..value_end]);
+        }
+        offset = value_start;
+    }
+    None
+}
+
+/// Remove markup tags, keeping their text content
+pub fn strip_tags(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for ch in text.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => result.push(ch),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Decode XML/HTML character references and the common named entities
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32)
+                }
+                _ if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+                _ => None,
+            };
+            ch.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((ch, end)) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON3: &str = include_str!("../../tests/fixtures/youtube/captions.json3");
+    const SRV3: &str = include_str!("../../tests/fixtures/youtube/captions.srv3.xml");
+    const XML: &str = include_str!("../../tests/fixtures/youtube/captions.xml");
+
+    #[test]
+    fn test_parse_json3_fixture() {
+        let segments = parse_timedtext(JSON3).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "welcome back to the channel");
+        assert_eq!(segments[0].start, 0.32);
+        assert_eq!(segments[0].duration, 4.16);
+        assert_eq!(segments[1].text, "today we're looking at ownership");
+        assert_eq!(segments[2].start, 6.1);
+    }
+
+    #[test]
+    fn test_parse_srv3_fixture() {
+        let segments = parse_timedtext(SRV3).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "welcome back to the channel");
+        assert_eq!(segments[1].text, "today we're looking at ownership & borrowing");
+        assert_eq!(segments[1].start, 2.5);
+        assert_eq!(segments[1].duration, 3.6);
+        assert_eq!(segments[2].text, "let's get started");
+    }
+
+    #[test]
+    fn test_parse_xml_fixture() {
+        let segments = parse_timedtext(XML).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].text, "Welcome back to the channel.");
+        assert_eq!(segments[1].text, "Today we're looking at \"ownership\" & borrowing.");
+        assert_eq!(segments[2].start, 6.1);
+        assert_eq!(segments[2].duration, 2.25);
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("a &amp; b &#39;c&#x27; &lt;d&gt;"), "a & b 'c' <d>");
+        assert_eq!(decode_entities("AT&T & co"), "AT&T & co");
+    }
+}
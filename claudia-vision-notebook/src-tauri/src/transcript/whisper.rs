@@ -0,0 +1,262 @@
+use async_trait::async_trait;
+use log::{debug, info};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, RwLock};
+
+use super::provider::{TranscriptProvider, TranscriptTrack};
+use super::subtitles::parse_timestamp;
+use super::youtube::{fetch_watch_page, http_client};
+use crate::commands::vision_notebook::{TranscriptSegment, VideoInfo};
+use crate::media::extract_audio;
+
+/// whisper.cpp's command-line program, looked up on PATH when no binary is configured
+const DEFAULT_BINARY: &str = "whisper-cli";
+
+/// Where local speech-to-text finds whisper.cpp and its model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WhisperConfig {
+    /// whisper.cpp-compatible binary; defaults to `whisper-cli`
+    pub binary_path: Option<String>,
+    /// ggml model file, e.g. `ggml-base.en.bin`
+    pub model_path: Option<String>,
+}
+
+impl WhisperConfig {
+    pub fn binary(&self) -> &str {
+        self.binary_path.as_deref().filter(|path| !path.is_empty()).unwrap_or(DEFAULT_BINARY)
+    }
+
+    /// The model path, or an error explaining what to configure
+    pub fn model(&self) -> Result<&Path, String> {
+        let model = self.model_path.as_deref()
+            .filter(|path| !path.is_empty())
+            .map(Path::new)
+            .ok_or_else(|| "Local transcription is not configured: set a whisper model path".to_string())?;
+        if !model.exists() {
+            return Err(format!("Whisper model not found: {}", model.display()));
+        }
+        Ok(model)
+    }
+}
+
+/// Configuration shared by the provider and the settings commands
+pub type SharedWhisperConfig = Arc<RwLock<WhisperConfig>>;
+
+/// Text and language recognized from an audio file
+#[derive(Debug)]
+pub struct WhisperTranscript {
+    pub segments: Vec<TranscriptSegment>,
+    /// Language passed in or detected by whisper
+    pub language: Option<String>,
+}
+
+/// Provider that downloads a video's audio and transcribes it locally
+///
+/// Registered last, so it only runs for videos without usable captions.
+pub struct WhisperProvider {
+    config: SharedWhisperConfig,
+}
+
+impl WhisperProvider {
+    pub fn new(config: SharedWhisperConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl TranscriptProvider for WhisperProvider {
+    fn name(&self) -> &'static str {
+        "whisper"
+    }
+
+    async fn fetch(&self, video_id: &str, language: Option<&str>) -> Result<(VideoInfo, TranscriptTrack), String> {
+        let config = self.config.read().map_err(|e| e.to_string())?.clone();
+        // Fail before downloading anything when whisper cannot run
+        config.model()?;
+
+        let client = http_client()?;
+        let page = fetch_watch_page(&client, video_id).await?;
+
+        let workdir = tempfile::tempdir()
+            .map_err(|e| format!("Failed to create temporary directory: {}", e))?;
+        let downloaded = download_audio(video_id, workdir.path()).await?;
+        let audio_path = workdir.path().join("audio.wav");
+        extract_audio(&downloaded, &audio_path).await?;
+
+        info!("Transcribing {} with {}", video_id, config.binary());
+        let transcript = transcribe_audio(&config, &audio_path, language).await?;
+
+        Ok((
+            page.video_info,
+            TranscriptTrack {
+                segments: transcript.segments,
+                language: transcript.language,
+                is_auto_generated: true,
+                is_machine_transcribed: true,
+                chapters: page.chapters,
+            },
+        ))
+    }
+}
+
+/// Run whisper on a 16 kHz mono WAV file
+///
+/// Without a `language` whisper detects it.
+pub async fn transcribe_audio(
+    config: &WhisperConfig,
+    audio_path: &Path,
+    language: Option<&str>,
+) -> Result<WhisperTranscript, String> {
+    let model = config.model()?;
+
+    let output = tokio::process::Command::new(config.binary())
+        .arg("-m")
+        .arg(model)
+        .arg("-f")
+        .arg(audio_path)
+        .arg("-l")
+        .arg(language.unwrap_or("auto"))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute {}: {}", config.binary(), e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!("whisper failed: {}", stderr.trim()));
+    }
+
+    let segments = parse_whisper_output(&String::from_utf8_lossy(&output.stdout));
+    debug!("whisper produced {} segments", segments.len());
+
+    Ok(WhisperTranscript {
+        segments,
+        language: language.map(|l| l.to_string()).or_else(|| parse_detected_language(&stderr)),
+    })
+}
+
+/// Parse whisper.cpp's `[00:00:01.000 --> 00:00:04.500]  text` lines
+pub fn parse_whisper_output(stdout: &str) -> Vec<TranscriptSegment> {
+    let Ok(line_re) = Regex::new(r"^\[\s*([0-9:.,]+)\s*-->\s*([0-9:.,]+)\s*\]\s*(.*)$") else {
+        return Vec::new();
+    };
+
+    stdout
+        .lines()
+        .filter_map(|line| line_re.captures(line.trim()))
+        .filter_map(|caps| {
+            let start = parse_timestamp(&caps[1])?;
+            let end = parse_timestamp(&caps[2])?;
+            let text = caps[3].trim();
+            // Non-speech markers such as [BLANK_AUDIO] or (music) carry no words
+            let marker = (text.starts_with('[') && text.ends_with(']'))
+                || (text.starts_with('(') && text.ends_with(')'));
+            if text.is_empty() || marker {
+                return None;
+            }
+            Some(TranscriptSegment {
+                text: text.to_string(),
+                start,
+                duration: (end - start).max(0.0),
+            })
+        })
+        .collect()
+}
+
+/// Language whisper reports on stderr when asked to detect it
+fn parse_detected_language(stderr: &str) -> Option<String> {
+    let re = Regex::new(r"auto-detected language:\s*([a-z]{2,3})").ok()?;
+    re.captures(stderr).map(|caps| caps[1].to_string())
+}
+
+/// Download the best audio stream of a YouTube video into `dir`
+async fn download_audio(video_id: &str, dir: &Path) -> Result<PathBuf, String> {
+    let output = tokio::process::Command::new("yt-dlp")
+        .arg("-f")
+        .arg("bestaudio/best")
+        .arg("-o")
+        .arg(dir.join("source.%(ext)s"))
+        .arg(format!("https://www.youtube.com/watch?v={}", video_id))
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to execute yt-dlp: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("yt-dlp failed: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+
+    std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read download directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| path.file_stem().is_some_and(|stem| stem == "source"))
+        .ok_or_else(|| "yt-dlp did not write an audio file".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHISPER_OUTPUT: &str = include_str!("../../tests/fixtures/whisper/output.txt");
+
+    #[test]
+    fn test_parse_whisper_output_fixture() {
+        let segments = parse_whisper_output(WHISPER_OUTPUT);
+        let texts: Vec<&str> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec![
+            "Welcome to the onboarding session.",
+            "Today we walk through the deploy pipeline.",
+            "First, the build stage.",
+        ]);
+        assert_eq!(segments[1].start, 3.5);
+        assert_eq!(segments[1].duration, 4.25);
+        assert_eq!(segments[2].start, 3725.0);
+    }
+
+    #[test]
+    fn test_config_requires_model() {
+        let config = WhisperConfig::default();
+        assert_eq!(config.binary(), "whisper-cli");
+        assert!(config.model().unwrap_err().contains("not configured"));
+
+        let config = WhisperConfig { binary_path: None, model_path: Some("/nonexistent/ggml.bin".to_string()) };
+        assert!(config.model().unwrap_err().starts_with("Whisper model not found"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_transcribe_with_stub_binary() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/whisper/output.txt");
+        let binary = dir.path().join("whisper-stub");
+        std::fs::write(
+            &binary,
+            format!(
+                "#!/bin/sh\necho 'whisper_full_with_state: auto-detected language: en (p = 0.972)' >&2\ncat '{}'\n",
+                fixture.display()
+            ),
+        ).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let model = dir.path().join("ggml-base.en.bin");
+        std::fs::write(&model, b"").unwrap();
+        let audio = dir.path().join("audio.wav");
+        std::fs::write(&audio, b"").unwrap();
+
+        let config = WhisperConfig {
+            binary_path: Some(binary.to_string_lossy().to_string()),
+            model_path: Some(model.to_string_lossy().to_string()),
+        };
+        let transcript = transcribe_audio(&config, &audio, None).await.unwrap();
+        assert_eq!(transcript.segments.len(), 3);
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+
+        let transcript = transcribe_audio(&config, &audio, Some("de")).await.unwrap();
+        assert_eq!(transcript.language.as_deref(), Some("de"));
+    }
+}
@@ -0,0 +1,378 @@
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use super::timedtext::{parse_timedtext, TimedTextFormat};
+use super::{TranscriptProvider, TranscriptTrack};
+use crate::chapters::parse_description_chapters;
+use crate::commands::vision_notebook::{TranscriptSegment, VideoInfo};
+use crate::manifest::Chapter;
+
+const WATCH_URL: &str = "https://www.youtube.com/watch";
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36";
+
+/// Languages tried, in order, when no language is requested
+const DEFAULT_LANGUAGES: &[&str] = &["en", "en-US", "en-GB"];
+
+/// A caption track advertised by the watch page
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptionTrack {
+    #[serde(skip)]
+    pub base_url: String,
+    pub language_code: String,
+    pub name: String,
+    /// True for YouTube's automatic speech recognition tracks
+    pub is_auto_generated: bool,
+}
+
+/// The parts of a watch page needed to fetch a transcript
+#[derive(Debug)]
+pub struct WatchPage {
+    pub video_info: VideoInfo,
+    pub caption_tracks: Vec<CaptionTrack>,
+    /// Chapters listed in the description
+    pub chapters: Vec<Chapter>,
+}
+
+/// Provider backed by the captions published on the YouTube watch page
+pub struct YouTubeCaptionProvider;
+
+#[async_trait]
+impl TranscriptProvider for YouTubeCaptionProvider {
+    fn name(&self) -> &'static str {
+        "youtube_captions"
+    }
+
+    async fn fetch(&self, video_id: &str, language: Option<&str>) -> Result<(VideoInfo, TranscriptTrack), String> {
+        fetch_transcript(video_id, language).await
+    }
+}
+
+/// Fetch video metadata and a caption track, in `language` if given
+pub async fn fetch_transcript(
+    video_id: &str,
+    language: Option<&str>,
+) -> Result<(VideoInfo, TranscriptTrack), String> {
+    let client = http_client()?;
+    let page = fetch_watch_page(&client, video_id).await?;
+
+    let track = match language {
+        Some(lang) => select_caption_track(&page.caption_tracks, &[lang])
+            .filter(|track| matches_language(track, lang))
+            .ok_or_else(|| format!("No {} captions available for this video", lang))?,
+        None => select_caption_track(&page.caption_tracks, DEFAULT_LANGUAGES)
+            .ok_or_else(|| "No captions available for this video".to_string())?,
+    };
+    debug!("Using caption track {} ({})", track.language_code, track.name);
+
+    let segments = fetch_caption_track(&client, track).await?;
+    Ok((
+        page.video_info,
+        TranscriptTrack {
+            segments,
+            language: Some(track.language_code.clone()),
+            is_auto_generated: track.is_auto_generated,
+            is_machine_transcribed: false,
+            chapters: page.chapters,
+        },
+    ))
+}
+
+/// List the caption tracks a video offers
+pub async fn list_caption_tracks(video_id: &str) -> Result<Vec<CaptionTrack>, String> {
+    let client = http_client()?;
+    Ok(fetch_watch_page(&client, video_id).await?.caption_tracks)
+}
+
+pub(crate) fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Download and parse the watch page for a video
+pub async fn fetch_watch_page(client: &reqwest::Client, video_id: &str) -> Result<WatchPage, String> {
+    let html = client
+        .get(WATCH_URL)
+        .query(&[("v", video_id), ("hl", "en")])
+        .header("Accept-Language", "en-US,en;q=0.9")
+        // Skip the EU consent interstitial, which has no player response
+        .header("Cookie", "CONSENT=YES+cb")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch watch page: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch watch page: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read watch page: {}", e))?;
+
+    parse_watch_page(video_id, &html)
+}
+
+/// Download a caption track, trying each timedtext format until one yields segments
+pub async fn fetch_caption_track(
+    client: &reqwest::Client,
+    track: &CaptionTrack,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let mut last_error = "Caption track was empty".to_string();
+
+    for format in [TimedTextFormat::Json3, TimedTextFormat::Srv3, TimedTextFormat::Xml] {
+        let url = caption_url(&track.base_url, format);
+        let body = match client.get(&url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response.text().await.unwrap_or_default(),
+            Err(e) => {
+                last_error = format!("Failed to download captions: {}", e);
+                continue;
+            }
+        };
+
+        if body.trim().is_empty() {
+            continue;
+        }
+
+        // The server may answer in a different format than requested
+        match parse_timedtext(&body) {
+            Ok(segments) if !segments.is_empty() => return Ok(segments),
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Failed to parse {:?} captions: {}", format, e);
+                last_error = e;
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Build the timedtext URL for a track in the given format
+pub fn caption_url(base_url: &str, format: TimedTextFormat) -> String {
+    let (path, query) = base_url.split_once('?').unwrap_or((base_url, ""));
+    let mut params: Vec<&str> = query
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("fmt="))
+        .collect();
+
+    let fmt_param;
+    if let Some(fmt) = format.query_value() {
+        fmt_param = format!("fmt={}", fmt);
+        params.push(&fmt_param);
+    }
+
+    if params.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, params.join("&"))
+    }
+}
+
+/// Extract video details and caption tracks from watch page HTML
+pub fn parse_watch_page(video_id: &str, html: &str) -> Result<WatchPage, String> {
+    let player_json = extract_json_object(html, "ytInitialPlayerResponse")
+        .ok_or_else(|| "Could not find player response in watch page".to_string())?;
+    let player: JsonValue = serde_json::from_str(player_json)
+        .map_err(|e| format!("Failed to parse player response: {}", e))?;
+
+    let status = player["playabilityStatus"]["status"].as_str().unwrap_or("OK");
+    if status != "OK" {
+        let reason = player["playabilityStatus"]["reason"].as_str().unwrap_or(status);
+        return Err(format!("Video is unavailable: {}", reason));
+    }
+
+    Ok(WatchPage {
+        video_info: video_info_from_player(video_id, &player),
+        caption_tracks: caption_tracks_from_player(&player),
+        chapters: parse_description_chapters(
+            player["videoDetails"]["shortDescription"].as_str().unwrap_or_default(),
+            player["videoDetails"]["lengthSeconds"].as_str().and_then(|s| s.parse::<f64>().ok()),
+        ),
+    })
+}
+
+fn video_info_from_player(video_id: &str, player: &JsonValue) -> VideoInfo {
+    let details = &player["videoDetails"];
+
+    let thumbnail_url = details["thumbnail"]["thumbnails"]
+        .as_array()
+        .and_then(|thumbs| thumbs.last())
+        .and_then(|thumb| thumb["url"].as_str())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| format!("https://img.youtube.com/vi/{}/maxresdefault.jpg", video_id));
+
+    VideoInfo {
+        video_id: details["videoId"].as_str().unwrap_or(video_id).to_string(),
+        title: details["title"].as_str().unwrap_or("Unknown Title").to_string(),
+        channel: details["author"].as_str().unwrap_or("Unknown Channel").to_string(),
+        duration: details["lengthSeconds"]
+            .as_str()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(iso8601_duration),
+        thumbnail_url: Some(thumbnail_url),
+    }
+}
+
+fn caption_tracks_from_player(player: &JsonValue) -> Vec<CaptionTrack> {
+    let tracks = match player["captions"]["playerCaptionsTracklistRenderer"]["captionTracks"].as_array() {
+        Some(tracks) => tracks,
+        None => return Vec::new(),
+    };
+
+    tracks
+        .iter()
+        .filter_map(|track| {
+            let base_url = track["baseUrl"].as_str()?.to_string();
+            let language_code = track["languageCode"].as_str()?.to_string();
+            let name = track["name"]["simpleText"]
+                .as_str()
+                .or_else(|| track["name"]["runs"][0]["text"].as_str())
+                .unwrap_or(&language_code)
+                .to_string();
+            Some(CaptionTrack {
+                base_url,
+                language_code,
+                name,
+                is_auto_generated: track["kind"].as_str() == Some("asr"),
+            })
+        })
+        .collect()
+}
+
+/// Pick the best caption track: preferred languages first, manual over auto-generated
+pub fn select_caption_track<'a>(tracks: &'a [CaptionTrack], preferred: &[&str]) -> Option<&'a CaptionTrack> {
+    let rank = |track: &CaptionTrack| {
+        let language = preferred
+            .iter()
+            .position(|lang| matches_language(track, lang))
+            .unwrap_or(usize::MAX);
+        let inexact = !preferred.iter().any(|lang| track.language_code.eq_ignore_ascii_case(lang));
+        (language, track.is_auto_generated, inexact)
+    };
+
+    tracks.iter().min_by_key(|track| rank(track))
+}
+
+/// Whether a track is in `language`; regional variants (en-US) count as their base language (en)
+fn matches_language(track: &CaptionTrack, language: &str) -> bool {
+    let base = track.language_code.split('-').next().unwrap_or("");
+    track.language_code.eq_ignore_ascii_case(language) || base.eq_ignore_ascii_case(language)
+}
+
+/// Find `marker = {...}` in a page and return the balanced JSON object
+pub(crate) fn extract_json_object<'a>(html: &'a str, marker: &str) -> Option<&'a str> {
+    let marker_pos = html.find(marker)?;
+    let start = marker_pos + html[marker_pos..].find('{')?;
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, byte) in html.as_bytes()[start..].iter().enumerate() {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&html[start..start + i + 1]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Short link that opens a video at a position, e.g. `https://youtu.be/<id>?t=95`
+pub fn timestamp_url(video_id: &str, seconds: f64) -> String {
+    format!("https://youtu.be/{}?t={}", video_id, seconds.max(0.0).floor() as u64)
+}
+
+/// Format a length in seconds as an ISO 8601 duration (e.g. `PT12M34S`)
+pub fn iso8601_duration(total_seconds: u64) -> String {
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    let mut duration = "PT".to_string();
+    if hours > 0 {
+        duration.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        duration.push_str(&format!("{}M", minutes));
+    }
+    if seconds > 0 || total_seconds == 0 {
+        duration.push_str(&format!("{}S", seconds));
+    }
+    duration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WATCH_PAGE: &str = include_str!("../../tests/fixtures/youtube/watch_page.html");
+
+    #[test]
+    fn test_parse_watch_page_fixture() {
+        let page = parse_watch_page("abcDEF12345", WATCH_PAGE).unwrap();
+
+        assert_eq!(page.video_info.title, "Rust Ownership Explained {in 10 minutes}");
+        assert_eq!(page.video_info.channel, "Fixture Channel");
+        assert_eq!(page.video_info.duration.as_deref(), Some("PT12M34S"));
+        assert_eq!(
+            page.video_info.thumbnail_url.as_deref(),
+            Some("https://i.ytimg.com/vi/abcDEF12345/maxresdefault.jpg")
+        );
+
+        let languages: Vec<&str> = page.caption_tracks.iter().map(|t| t.language_code.as_str()).collect();
+        assert_eq!(languages, vec!["en", "de", "en-US"]);
+        assert!(page.caption_tracks[0].is_auto_generated);
+        assert_eq!(page.caption_tracks[1].name, "German");
+
+        let chapters: Vec<(&str, f64)> = page.chapters.iter().map(|c| (c.title.as_str(), c.start)).collect();
+        assert_eq!(chapters, vec![("Intro", 0.0), ("Moves", 90.0), ("Borrowing", 300.0)]);
+        assert_eq!(page.chapters[2].end, Some(754.0));
+    }
+
+    #[test]
+    fn test_select_caption_track_prefers_manual_tracks() {
+        let page = parse_watch_page("abcDEF12345", WATCH_PAGE).unwrap();
+
+        // The manual en-US track beats the auto-generated en track
+        let track = select_caption_track(&page.caption_tracks, &["en"]).unwrap();
+        assert_eq!(track.language_code, "en-US");
+
+        let track = select_caption_track(&page.caption_tracks, &["de", "en"]).unwrap();
+        assert_eq!(track.language_code, "de");
+
+        // Without a match the best remaining track is still returned
+        let track = select_caption_track(&page.caption_tracks, &["ja"]).unwrap();
+        assert!(!matches_language(track, "ja"));
+    }
+
+    #[test]
+    fn test_parse_watch_page_unplayable() {
+        let html = r#"<script>var ytInitialPlayerResponse = {"playabilityStatus":{"status":"LOGIN_REQUIRED","reason":"Sign in to confirm your age"}};</script>"#;
+        let err = parse_watch_page("abcDEF12345", html).unwrap_err();
+        assert!(err.contains("Sign in to confirm your age"));
+    }
+
+    #[test]
+    fn test_caption_url_replaces_format() {
+        let url = caption_url("https://www.youtube.com/api/timedtext?v=x&lang=en&fmt=srv1", TimedTextFormat::Json3);
+        assert_eq!(url, "https://www.youtube.com/api/timedtext?v=x&lang=en&fmt=json3");
+
+        let url = caption_url("https://www.youtube.com/api/timedtext?v=x&fmt=srv1", TimedTextFormat::Xml);
+        assert_eq!(url, "https://www.youtube.com/api/timedtext?v=x");
+    }
+}
@@ -0,0 +1,430 @@
+use log::warn;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::commands::quiz::SavedQuiz;
+use crate::commands::vision_notebook::{source_link, TranscriptHistoryDetails};
+use crate::manifest::{Chapter, Manifest};
+use crate::transcript::subtitles::format_clock;
+
+/// Folder inside a video's export holding the frames its note embeds
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// A video rendered as one Markdown note
+pub struct VaultNote {
+    /// File and folder name, from the title and video id
+    pub name: String,
+    pub markdown: String,
+    /// Frames the note embeds: where they are now and their name in `attachments/`
+    pub attachments: Vec<(PathBuf, String)>,
+}
+
+/// Render a video's notebook as an Obsidian-friendly Markdown note
+///
+/// The output depends only on what is stored, so exporting an unchanged
+/// video again produces the same note. `media_dir` resolves relative frame
+/// paths, normally the folder holding the manifest.
+pub fn render_note(
+    details: &TranscriptHistoryDetails,
+    quizzes: &[SavedQuiz],
+    manifest: Option<&Manifest>,
+    media_dir: Option<&Path>,
+) -> VaultNote {
+    let mut note = NoteWriter {
+        details,
+        manifest,
+        media_dir,
+        markdown: String::new(),
+        attachments: Vec::new(),
+        names: HashMap::new(),
+    };
+
+    note.front_matter();
+    let _ = writeln!(note.markdown, "# {}\n", one_line(&details.title));
+
+    if let Some(summary) = details.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        let _ = writeln!(note.markdown, "## Summary\n\n{}\n", summary.trim());
+    }
+    if !details.chapters.is_empty() {
+        note.chapters();
+    }
+    if !details.annotations.is_empty() {
+        note.annotations();
+    }
+    if quizzes.iter().any(|quiz| !quiz.questions.is_empty()) {
+        note.quizzes(quizzes);
+    }
+    note.transcript();
+
+    VaultNote {
+        name: file_name(&details.title, &details.video_id),
+        markdown: note.markdown.trim_end().to_string() + "\n",
+        attachments: note.attachments,
+    }
+}
+
+/// Write a note to `<vault_dir>/<name>/<name>.md` with its frames, returning the note path
+///
+/// Unchanged files are left alone and frames the note no longer embeds are
+/// removed from `attachments/`, so exporting twice changes nothing.
+pub fn write_note(vault_dir: &Path, note: &VaultNote) -> Result<PathBuf, String> {
+    let dir = vault_dir.join(&note.name);
+    let attachments_dir = dir.join(ATTACHMENTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    if !note.attachments.is_empty() {
+        std::fs::create_dir_all(&attachments_dir)
+            .map_err(|e| format!("Failed to create {}: {}", attachments_dir.display(), e))?;
+    }
+    for (source, name) in &note.attachments {
+        let target = attachments_dir.join(name);
+        let Ok(content) = std::fs::read(source) else {
+            warn!("Frame {} is missing; its embed will not resolve", source.display());
+            continue;
+        };
+        if std::fs::read(&target).ok().as_deref() != Some(content.as_slice()) {
+            std::fs::write(&target, content)
+                .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+        }
+    }
+
+    if let Ok(entries) = std::fs::read_dir(&attachments_dir) {
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string());
+            let used = note.attachments.iter().any(|(_, used)| Some(used) == name.as_ref());
+            if path.is_file() && !used {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+
+    let path = dir.join(format!("{}.md", note.name));
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(note.markdown.as_str()) {
+        std::fs::write(&path, &note.markdown)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// A note name unique to the video, safe for file names on every platform and in wiki links
+///
+/// The video id is kept next to the title because titles like "Lecture 1"
+/// repeat, and two videos must never share a note folder.
+pub fn file_name(title: &str, video_id: &str) -> String {
+    let title = safe_name(title);
+    let video_id = safe_name(video_id);
+    match (title.is_empty(), video_id.is_empty()) {
+        (true, _) => video_id,
+        (false, true) => title,
+        (false, false) => format!("{} ({})", title, video_id),
+    }
+}
+
+fn safe_name(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .map(|c| if "/\\:*?\"<>|#^[]".contains(c) || c.is_control() { ' ' } else { c })
+        .collect();
+    let name: String = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name: String = name.trim_matches('.').chars().take(100).collect();
+    name.trim().to_string()
+}
+
+struct NoteWriter<'a> {
+    details: &'a TranscriptHistoryDetails,
+    manifest: Option<&'a Manifest>,
+    media_dir: Option<&'a Path>,
+    markdown: String,
+    attachments: Vec<(PathBuf, String)>,
+    /// Attachment names already used, by source path
+    names: HashMap<PathBuf, String>,
+}
+
+impl NoteWriter<'_> {
+    fn front_matter(&mut self) {
+        let d = self.details;
+        let mut fields = vec![
+            ("title", yaml_string(&d.title)),
+            ("channel", yaml_string(&d.channel)),
+            ("video_id", yaml_string(&d.video_id)),
+            ("url", yaml_string(&d.video_url)),
+            ("source", yaml_string(&d.source_type)),
+        ];
+        if let Some(duration) = &d.duration {
+            fields.push(("duration", yaml_string(duration)));
+        }
+        if let Some(language) = &d.language_code {
+            fields.push(("language", yaml_string(language)));
+        }
+        if let Some(thumbnail) = &d.thumbnail_url {
+            fields.push(("thumbnail", yaml_string(thumbnail)));
+        }
+        fields.push(("fetched", yaml_string(&d.fetched_at)));
+        fields.push(("tags", "[video]".to_string()));
+
+        self.markdown.push_str("---\n");
+        for (key, value) in fields {
+            let _ = writeln!(self.markdown, "{}: {}", key, value);
+        }
+        self.markdown.push_str("---\n\n");
+    }
+
+    fn chapters(&mut self) {
+        let details = self.details;
+        self.markdown.push_str("## Chapters\n\n");
+        for chapter in &details.chapters {
+            let _ = writeln!(self.markdown, "- {} {}", self.link(chapter.start), one_line(&chapter.title));
+            if let Some(embed) = self.frame_embed(chapter.start) {
+                let _ = writeln!(self.markdown, "  {}", embed);
+            }
+        }
+        self.markdown.push('\n');
+    }
+
+    fn annotations(&mut self) {
+        let details = self.details;
+        self.markdown.push_str("## Annotations\n\n");
+        for annotation in &details.annotations {
+            let tags: String = annotation.tags.iter()
+                .map(|tag| format!(" #{}", tag.split_whitespace().collect::<Vec<_>>().join("-")))
+                .collect();
+            let _ = writeln!(self.markdown, "- {} {}{}", self.link(annotation.t), one_line(&annotation.text), tags);
+            let frame = annotation.frame_path.as_deref().and_then(|path| self.attach(Path::new(path)));
+            if let Some(name) = frame {
+                let _ = writeln!(self.markdown, "  ![[{}/{}]]", ATTACHMENTS_DIR, name);
+            }
+        }
+        self.markdown.push('\n');
+    }
+
+    fn quizzes(&mut self, quizzes: &[SavedQuiz]) {
+        self.markdown.push_str("## Quiz\n");
+        for quiz in quizzes.iter().filter(|quiz| !quiz.questions.is_empty()) {
+            let _ = writeln!(self.markdown, "\n### {}\n", one_line(&quiz.title));
+            for (i, question) in quiz.questions.iter().enumerate() {
+                let at = question.timestamp.map(|t| format!(" ({})", self.link(t))).unwrap_or_default();
+                let _ = writeln!(self.markdown, "{}. {}{}", i + 1, one_line(&question.question), at);
+                for option in &question.options {
+                    let _ = writeln!(self.markdown, "    - {}", one_line(option));
+                }
+                let answer = question.options.get(question.correct_answer).map(String::as_str).unwrap_or("?");
+                let explanation = one_line(&question.explanation);
+                let _ = writeln!(
+                    self.markdown,
+                    "\n    **Answer:** {}{}{}\n",
+                    one_line(answer),
+                    if explanation.is_empty() { "" } else { ". " },
+                    explanation
+                );
+                if let Some(embed) = question.timestamp.and_then(|t| self.frame_embed(t)) {
+                    let _ = writeln!(self.markdown, "    {}\n", embed);
+                }
+            }
+        }
+        self.markdown.push('\n');
+    }
+
+    fn transcript(&mut self) {
+        let details = self.details;
+        let segments = details.cleaned_transcript.as_ref().unwrap_or(&details.transcript);
+        self.markdown.push_str("## Transcript\n");
+
+        let mut chapter: Option<&Chapter> = None;
+        let mut started = false;
+        for segment in segments {
+            let current = details.chapters.iter().rev().find(|c| c.start <= segment.start);
+            if !started || current.map(|c| c.start) != chapter.map(|c| c.start) {
+                chapter = current;
+                started = true;
+                match chapter {
+                    Some(chapter) => {
+                        let _ = writeln!(self.markdown, "\n### {}\n", one_line(&chapter.title));
+                    }
+                    None => self.markdown.push('\n'),
+                }
+            }
+            let _ = writeln!(self.markdown, "- {} {}", self.link(segment.start), one_line(&segment.text));
+        }
+    }
+
+    /// `[MM:SS](link)` opening the video at `t`
+    fn link(&self, t: f64) -> String {
+        let d = self.details;
+        format!("[{}]({})", format_clock(t), source_link(&d.source_type, &d.video_id, &d.video_url, t))
+    }
+
+    /// Embed of the frame on screen at `t`, if the video has frames
+    fn frame_embed(&mut self, t: f64) -> Option<String> {
+        let path = self.manifest?.frame_at(t)?.to_string();
+        let name = self.attach(Path::new(&path))?;
+        Some(format!("![[{}/{}]]", ATTACHMENTS_DIR, name))
+    }
+
+    /// Register a frame to copy and return its name in `attachments/`
+    fn attach(&mut self, path: &Path) -> Option<String> {
+        let source = match self.media_dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+        if let Some(name) = self.names.get(&source) {
+            return Some(name.clone());
+        }
+
+        let file_name = source.file_name()?.to_string_lossy().to_string();
+        let taken = |name: &String| self.attachments.iter().any(|(_, used)| used == name);
+        let mut name = file_name.clone();
+        let mut n = 2;
+        while taken(&name) {
+            name = format!("{}-{}", n, file_name);
+            n += 1;
+        }
+
+        self.names.insert(source.clone(), name.clone());
+        self.attachments.push((source, name.clone()));
+        Some(name)
+    }
+}
+
+/// A double-quoted YAML scalar
+fn yaml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', " "))
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::annotations::TranscriptAnnotation;
+    use crate::commands::vision_notebook::{QuizQuestion, TranscriptSegment, VideoInfo};
+    use crate::media::SampledFrame;
+
+    fn details() -> TranscriptHistoryDetails {
+        TranscriptHistoryDetails {
+            id: 1,
+            video_id: "dQw4w9WgXcQ".to_string(),
+            video_url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            title: "Rust: \"Ownership\" / Borrowing".to_string(),
+            channel: "Rust Talks".to_string(),
+            duration: Some("PT10M".to_string()),
+            thumbnail_url: None,
+            transcript: vec![
+                TranscriptSegment { text: "welcome".to_string(), start: 0.0, duration: 5.0 },
+                TranscriptSegment { text: "borrowing rules".to_string(), start: 65.0, duration: 5.0 },
+            ],
+            manifest_path: None,
+            provider: None,
+            source_type: "youtube".to_string(),
+            language_code: Some("en".to_string()),
+            is_machine_transcribed: false,
+            chapters: vec![
+                Chapter { title: "Intro".to_string(), start: 0.0, end: Some(60.0) },
+                Chapter { title: "Borrowing".to_string(), start: 60.0, end: None },
+            ],
+            chapter_source: None,
+            cleaned_transcript: None,
+            annotations: vec![TranscriptAnnotation {
+                id: 1,
+                history_id: 1,
+                t: 66.0,
+                text: "Key rule".to_string(),
+                tags: vec!["exam prep".to_string()],
+                frame_path: Some("frames/frame_000033.jpg".to_string()),
+                created_at: String::new(),
+                updated_at: String::new(),
+            }],
+            summary: Some("A talk about ownership.".to_string()),
+            fetched_at: "2024-05-01 10:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_note() {
+        let details = details();
+        let video = VideoInfo {
+            video_id: details.video_id.clone(),
+            title: details.title.clone(),
+            channel: details.channel.clone(),
+            duration: None,
+            thumbnail_url: None,
+        };
+        let frames: Vec<SampledFrame> = (0..40)
+            .map(|i| SampledFrame { path: format!("frames/frame_{:06}.jpg", i).into(), t: i as f64 * 2.0 })
+            .collect();
+        let manifest = Manifest::build(&video, &details.transcript, &frames, None, None);
+        let quiz = SavedQuiz {
+            id: 1,
+            history_id: 1,
+            video_id: details.video_id.clone(),
+            video_title: details.title.clone(),
+            title: "Check\nyourself".to_string(),
+            difficulty: None,
+            questions: vec![QuizQuestion {
+                question: "Who owns it?".to_string(),
+                options: vec!["One owner".to_string(), "Everyone".to_string()],
+                correct_answer: 0,
+                explanation: "Single ownership".to_string(),
+                timestamp: Some(66.0),
+            }],
+            created_at: String::new(),
+        };
+
+        let note = render_note(&details, &[quiz], Some(&manifest), Some(Path::new("/videos/dQw4w9WgXcQ")));
+        assert_eq!(note.name, "Rust Ownership Borrowing (dQw4w9WgXcQ)");
+        assert!(note.markdown.starts_with("---\ntitle: \"Rust: \\\"Ownership\\\" / Borrowing\"\n"));
+        assert!(note.markdown.contains("## Summary\n\nA talk about ownership.\n"));
+        assert!(note.markdown.contains("- [01:00](https://youtu.be/dQw4w9WgXcQ?t=60) Borrowing\n  ![[attachments/frame_000030.jpg]]"));
+        assert!(note.markdown.contains("- [01:06](https://youtu.be/dQw4w9WgXcQ?t=66) Key rule #exam-prep\n  ![[attachments/frame_000033.jpg]]"));
+        assert!(note.markdown.contains("### Check yourself\n"));
+        assert!(note.markdown.contains("1. Who owns it? ([01:06](https://youtu.be/dQw4w9WgXcQ?t=66))"));
+        assert!(note.markdown.contains("**Answer:** One owner. Single ownership"));
+        assert!(note.markdown.contains("### Borrowing\n\n- [01:05](https://youtu.be/dQw4w9WgXcQ?t=65) borrowing rules\n"));
+
+        // The annotation and quiz share a frame, which is copied once
+        let names: Vec<&str> = note.attachments.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, vec!["frame_000000.jpg", "frame_000030.jpg", "frame_000033.jpg"]);
+        assert_eq!(note.attachments[0].0, Path::new("/videos/dQw4w9WgXcQ/frames/frame_000000.jpg"));
+
+        let again = render_note(&details, &[], Some(&manifest), None);
+        assert!(!again.markdown.contains("## Quiz"));
+    }
+
+    #[test]
+    fn test_write_note_is_idempotent() {
+        let media = tempfile::tempdir().unwrap();
+        let vault = tempfile::tempdir().unwrap();
+        let frame = media.path().join("frame_000001.jpg");
+        std::fs::write(&frame, b"jpeg").unwrap();
+
+        let note = VaultNote {
+            name: "Talk".to_string(),
+            markdown: "# Talk\n".to_string(),
+            attachments: vec![(frame.clone(), "frame_000001.jpg".to_string())],
+        };
+        let path = write_note(vault.path(), &note).unwrap();
+        assert_eq!(path, vault.path().join("Talk/Talk.md"));
+        let attachments = vault.path().join("Talk/attachments");
+        std::fs::write(attachments.join("frame_000099.jpg"), b"stale").unwrap();
+
+        write_note(vault.path(), &note).unwrap();
+        let mut files: Vec<String> = std::fs::read_dir(&attachments)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["frame_000001.jpg"]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Talk\n");
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("What's new in C++? [2024]", "id"), "What's new in C++ 2024 (id)");
+        assert_eq!(file_name("..///..", "abc123"), "abc123");
+        // Same title, different videos
+        assert_ne!(file_name("Lecture 1", "abc123"), file_name("Lecture 1", "def456"));
+    }
+}
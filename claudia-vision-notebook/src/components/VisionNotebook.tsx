@@ -1,4 +1,4 @@
-import React, { useState, useRef } from 'react';
+import React, { useState, useRef, useEffect } from 'react';
 import { Card, CardContent, CardHeader, CardTitle } from './ui/card';
 import { Button } from './ui/button';
 import { Input } from './ui/input';
@@ -58,7 +58,7 @@ export const VisionNotebook: React.FC = () => {
   const [currentTime, setCurrentTime] = useState(0);
   const [includeFrames, setIncludeFrames] = useState(false);
   const [fps, setFps] = useState(0.5);
-  const [projectDir] = useState('~/VisionNotebook');
+  const [libraryRoot, setLibraryRoot] = useState('');
   const [showHistory, setShowHistory] = useState(false);
   const [activeMainTab, setActiveMainTab] = useState('vision');
   const [statusMessage, setStatusMessage] = useState<{type: 'error' | 'success' | 'warning', message: string} | null>(null);
@@ -68,6 +68,12 @@ export const VisionNotebook: React.FC = () => {
   
   const videoRef = useRef<HTMLVideoElement>(null);
 
+  useEffect(() => {
+    invoke<{ resolved_path: string }>('get_library_root')
+      .then(root => setLibraryRoot(root.resolved_path))
+      .catch(error => console.error('Failed to load library root:', error));
+  }, []);
+
   const showStatus = (type: 'error' | 'success' | 'warning', message: string) => {
     setStatusMessage({ type, message });
     setTimeout(() => setStatusMessage(null), 5000);
@@ -85,7 +91,6 @@ export const VisionNotebook: React.FC = () => {
     try {
       const result: any = await invoke('process_youtube_video', {
         url: videoUrl,
-        projectDir,
         fps: includeFrames ? fps : null
       });
       
@@ -124,7 +129,9 @@ export const VisionNotebook: React.FC = () => {
       const answer: any = await invoke('ask_video_question', {
         question: currentQuestion,
         manifestPath,
-        contextWindow: 30
+        contextWindow: 30,
+        // Until the video has played, ask about the whole transcript
+        timestamp: currentTime > 0 ? currentTime : null
       });
       
       setChatHistory(prev => [...prev, {
@@ -485,7 +492,7 @@ export const VisionNotebook: React.FC = () => {
                 <SessionAutoSave
                   sessionId={currentSessionId}
                   sessionName={videoInfo?.title ? `Chat: ${videoInfo.title}` : 'Video Chat'}
-                  projectPath={projectDir}
+                  projectPath={libraryRoot}
                   model="claude-3"
                   messages={chatHistory.map(h => ({ 
                     role: 'user' as const, 